@@ -0,0 +1,156 @@
+use log::debug;
+use serde::Serialize;
+
+use crate::discovery::*;
+use crate::path_extensions::*;
+use crate::utils::git;
+use crate::utils::OutputFormat;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options<'a> {
+    pub search_path: &'a Path,
+    /// The git revision to compare against, changes are taken from its merge base with HEAD.
+    pub base: &'a str,
+    pub tests_only: bool,
+    pub exes_only: bool,
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub changed_files: Vec<PathBuf>,
+    pub projects: Vec<PathBuf>,
+}
+
+pub fn run(options: Options) {
+    let report = affected(options);
+
+    match options.format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(std::io::stdout(), &report).unwrap();
+            println!();
+        }
+        OutputFormat::Table => {
+            for path in report.projects.iter() {
+                println!("{}", path.display());
+            }
+        }
+    }
+}
+
+pub fn affected(options: Options) -> Report {
+    let Options {
+        search_path,
+        base,
+        tests_only,
+        exes_only,
+        ..
+    } = options;
+
+    let current_dir = std::env::current_dir().unwrap();
+    let Root {
+        root_dir,
+        search_path,
+    } = root_dir(search_path).unwrap();
+
+    let changed_files = changed_files(&root_dir, base);
+
+    let projects = find_projects(&root_dir);
+    let edges = project_edges(&projects, &root_dir);
+
+    let mut included = vec![false; projects.len()];
+    for changed_file in changed_files.iter() {
+        for (index, project) in projects.iter().enumerate() {
+            if !included[index] && project_is_affected_by(project, changed_file) {
+                debug!(
+                    "{} is affected by {}",
+                    project.path.display(),
+                    changed_file.display()
+                );
+                included[index] = true;
+            }
+        }
+
+        // Files belong to the projects in the nearest directory containing a project.
+        let owners = changed_file.ancestors().skip(1).find_map(|dir| {
+            let owners = projects
+                .iter()
+                .enumerate()
+                .filter(|(_, project)| project.path.parent() == Some(dir))
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>();
+            if owners.is_empty() {
+                None
+            } else {
+                Some(owners)
+            }
+        });
+        for index in owners.into_iter().flatten() {
+            included[index] = true;
+        }
+    }
+
+    follow_project_references(&edges, &mut included, true, false);
+
+    let mut affected_projects = projects
+        .iter()
+        .zip(included.iter())
+        .filter(|&(project, &included)| {
+            included
+                && project.path.starts_with(&search_path)
+                && (!tests_only || project.is_test())
+                && (!exes_only || project.is_exe)
+        })
+        .map(|(project, _)| relative_path(&current_dir, &project.path))
+        .collect::<Vec<_>>();
+    affected_projects.sort();
+
+    Report {
+        changed_files: changed_files
+            .iter()
+            .map(|path| relative_path(&current_dir, path))
+            .collect(),
+        projects: affected_projects,
+    }
+}
+
+/// Whether the file is the project itself or one of the props and targets files it imports, either explicitly
+/// or implicitly through the Directory.Build and Directory.Packages files in one of its ancestor directories.
+fn project_is_affected_by(project: &crate::csproj::Project, changed_file: &Path) -> bool {
+    if project.path == changed_file || project.imports.iter().any(|import| import == changed_file) {
+        return true;
+    }
+
+    let implicitly_imported = match changed_file.file_name().and_then(|name| name.to_str()) {
+        Some(name) => matches!(
+            name,
+            "Directory.Build.props" | "Directory.Build.targets" | "Directory.Packages.props"
+        ),
+        None => false,
+    };
+
+    match changed_file.parent() {
+        Some(dir) => implicitly_imported && project.path.starts_with(dir),
+        None => false,
+    }
+}
+
+/// Lists the files changed since the merge base of `base` and HEAD, including uncommitted and untracked files.
+fn changed_files(root_dir: &Path, base: &str) -> Vec<PathBuf> {
+    let merge_base = git(root_dir, &["merge-base", base, "HEAD"]);
+    let merge_base = merge_base.trim();
+
+    let mut changed_files = git(
+        root_dir,
+        &["diff", "--name-only", "--no-renames", merge_base],
+    )
+    .lines()
+    .chain(git(root_dir, &["ls-files", "--others", "--exclude-standard"]).lines())
+    .map(|line| root_dir.join(line).simplify())
+    .collect::<Vec<_>>();
+    changed_files.sort();
+    changed_files.dedup();
+    changed_files
+}
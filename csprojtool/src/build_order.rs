@@ -0,0 +1,140 @@
+use serde::Serialize;
+
+use crate::list::list;
+use crate::path_extensions::*;
+use crate::utils::OutputFormat;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options<'a> {
+    pub search_path: &'a Path,
+    pub follow_incoming_project_references: bool,
+    pub follow_outgoing_project_references: bool,
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    /// All projects, every project comes after its dependencies.
+    pub order: Vec<PathBuf>,
+    /// Projects grouped by the length of the longest dependency chain below them.
+    pub layers: Vec<Vec<PathBuf>>,
+    /// The longest dependency chain, in build order.
+    pub critical_path: Vec<PathBuf>,
+}
+
+pub fn run(options: Options) {
+    let report = build_order(options);
+
+    match options.format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(std::io::stdout(), &report).unwrap();
+            println!();
+        }
+        OutputFormat::Table => print_table(&report),
+    }
+}
+
+pub fn build_order(options: Options) -> Report {
+    let Options {
+        search_path,
+        follow_incoming_project_references,
+        follow_outgoing_project_references,
+        ..
+    } = options;
+
+    let current_dir = std::env::current_dir().unwrap();
+
+    let mut projects = list(crate::list::Options {
+        search_path,
+        follow_incoming_project_references,
+        follow_outgoing_project_references,
+    });
+    projects.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let path_to_index = projects
+        .iter()
+        .enumerate()
+        .map(|(index, project)| (project.path.as_path(), index))
+        .collect::<HashMap<_, _>>();
+
+    // References to projects outside of the listed projects are not part of the build.
+    let outgoing = projects
+        .iter()
+        .map(|project| {
+            project
+                .project_references
+                .iter()
+                .filter_map(|path| path_to_index.get(path.as_path()).copied())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let cycles = crate::graph::cycles(&outgoing);
+    if !cycles.is_empty() {
+        eprintln!("Unable to determine a build order, found project reference cycles:");
+        for cycle in cycles.iter() {
+            eprintln!("  cycle of {} project(s):", cycle.len());
+            for &index in cycle.iter() {
+                eprintln!(
+                    "    {}",
+                    relative_path(&current_dir, &projects[index].path).display()
+                );
+            }
+        }
+        std::process::exit(1);
+    }
+
+    let layers = crate::graph::layers(&outgoing);
+    let layer_count = layers.iter().max().map_or(0, |&max| max + 1);
+
+    let mut layer_indices = vec![Vec::new(); layer_count];
+    for (index, &layer) in layers.iter().enumerate() {
+        layer_indices[layer].push(index);
+    }
+
+    // Walk down from a project in the top layer through a dependency in every layer below it.
+    let mut critical_path = Vec::new();
+    let mut next = layer_indices.last().map(|indices| indices[0]);
+    while let Some(index) = next {
+        critical_path.push(index);
+        next = outgoing[index]
+            .iter()
+            .copied()
+            .filter(|&target| layers[target] + 1 == layers[index])
+            .min();
+    }
+    critical_path.reverse();
+
+    let path = |index: usize| relative_path(&current_dir, &projects[index].path);
+
+    Report {
+        order: layer_indices
+            .iter()
+            .flatten()
+            .map(|&index| path(index))
+            .collect(),
+        layers: layer_indices
+            .iter()
+            .map(|indices| indices.iter().map(|&index| path(index)).collect())
+            .collect(),
+        critical_path: critical_path.into_iter().map(path).collect(),
+    }
+}
+
+fn print_table(report: &Report) {
+    for (index, layer) in report.layers.iter().enumerate() {
+        println!("Layer {} ({} projects):", index, layer.len());
+        for path in layer.iter() {
+            println!("  {}", path.display());
+        }
+    }
+
+    println!();
+    println!("Critical path ({} projects):", report.critical_path.len());
+    for path in report.critical_path.iter() {
+        println!("  {}", path.display());
+    }
+}
@@ -0,0 +1,247 @@
+use serde::{Deserialize, Serialize};
+
+use crate::csproj::Project;
+use crate::discovery::*;
+use crate::path_extensions::*;
+use crate::utils::OutputFormat;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options<'a> {
+    /// Json file with the rules, globs in the rules are relative to its directory.
+    pub config_path: &'a Path,
+    pub search_path: &'a Path,
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    pub rules: Vec<RuleConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RuleConfig {
+    pub description: Option<String>,
+    #[serde(flatten)]
+    pub rule: Rule,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "rule", rename_all = "kebab-case")]
+pub enum Rule {
+    /// Projects matching `from` must not reference projects matching `to`.
+    Forbid { from: String, to: String },
+    /// Projects matching `to` may only be referenced by projects matching `from`.
+    OnlyFrom { from: String, to: String },
+    /// Test projects must not be referenced.
+    NoTestReferences,
+    /// Projects matching `from` must not reference packages matching `package`.
+    ForbidPackage { from: String, package: String },
+}
+
+impl RuleConfig {
+    fn display(&self) -> String {
+        if let Some(description) = &self.description {
+            return description.clone();
+        }
+        match &self.rule {
+            Rule::Forbid { from, to } => format!("{} must not reference {}", from, to),
+            Rule::OnlyFrom { from, to } => format!("{} may only be referenced from {}", to, from),
+            Rule::NoTestReferences => "test projects must not be referenced".to_string(),
+            Rule::ForbidPackage { from, package } => {
+                format!("{} must not reference package {}", from, package)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Violation {
+    pub rule: String,
+    pub from: PathBuf,
+    /// The referenced project path or package name.
+    pub to: String,
+    /// The line of the offending reference in `from`, if it could be found.
+    pub line: Option<u32>,
+}
+
+pub fn run(options: Options) {
+    let violations = check_architecture(options);
+
+    match options.format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(std::io::stdout(), &violations).unwrap();
+            println!();
+        }
+        OutputFormat::Table => {
+            for violation in violations.iter() {
+                let location = match violation.line {
+                    Some(line) => format!("{}:{}", violation.from.display(), line),
+                    None => violation.from.display().to_string(),
+                };
+                println!(
+                    "{}: references {}, violating \"{}\"",
+                    location, violation.to, violation.rule
+                );
+            }
+            if violations.is_empty() {
+                println!("No architecture violations found.");
+            }
+        }
+    }
+
+    if !violations.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+pub fn check_architecture(options: Options) -> Vec<Violation> {
+    let Options {
+        config_path,
+        search_path,
+        ..
+    } = options;
+
+    let config = read_config(config_path);
+    let config_dir = config_path.simplified_absolute().unwrap();
+    let config_dir = config_dir.parent().unwrap();
+
+    let current_dir = std::env::current_dir().unwrap();
+    let root_dir = root_dir(search_path).unwrap().root_dir;
+
+    let mut projects = find_projects(&root_dir);
+    projects.sort_by(|a, b| a.path.cmp(&b.path));
+    let edges = project_edges(&projects, &root_dir);
+
+    let matcher = |glob: &str| {
+        globset::Glob::new(glob)
+            .unwrap_or_else(|e| panic!("Invalid glob {} in {}: {}", glob, config_path.display(), e))
+            .compile_matcher()
+    };
+    let matches = |matcher: &globset::GlobMatcher, project: &Project| {
+        matcher.is_match(relative_path(config_dir, &project.path))
+    };
+
+    let mut violations = Vec::new();
+
+    for rule in config.rules.iter() {
+        let mut violation = |from: &Project, to: String, line: Option<u32>| {
+            violations.push(Violation {
+                rule: rule.display(),
+                from: relative_path(&current_dir, &from.path),
+                to,
+                line,
+            })
+        };
+
+        let project_violation = |from: &Project, to: &Project| {
+            (
+                relative_path(&current_dir, &to.path).display().to_string(),
+                project_reference_line(&from.path, &to.path),
+            )
+        };
+
+        match &rule.rule {
+            Rule::Forbid { from, to } => {
+                let (from_matcher, to_matcher) = (matcher(from), matcher(to));
+                for &(source, target) in edges.iter() {
+                    let (source, target) = (&projects[source], &projects[target]);
+                    if matches(&from_matcher, source) && matches(&to_matcher, target) {
+                        let (to, line) = project_violation(source, target);
+                        violation(source, to, line);
+                    }
+                }
+            }
+            Rule::OnlyFrom { from, to } => {
+                let (from_matcher, to_matcher) = (matcher(from), matcher(to));
+                for &(source, target) in edges.iter() {
+                    let (source, target) = (&projects[source], &projects[target]);
+                    if !matches(&from_matcher, source) && matches(&to_matcher, target) {
+                        let (to, line) = project_violation(source, target);
+                        violation(source, to, line);
+                    }
+                }
+            }
+            Rule::NoTestReferences => {
+                for &(source, target) in edges.iter() {
+                    let (source, target) = (&projects[source], &projects[target]);
+                    if target.is_test() {
+                        let (to, line) = project_violation(source, target);
+                        violation(source, to, line);
+                    }
+                }
+            }
+            Rule::ForbidPackage { from, package } => {
+                let (from_matcher, package_matcher) = (matcher(from), matcher(package));
+                for project in projects
+                    .iter()
+                    .filter(|project| matches(&from_matcher, project))
+                {
+                    for package in project
+                        .package_references
+                        .iter()
+                        .filter(|package| package_matcher.is_match(&package.name))
+                    {
+                        violation(
+                            project,
+                            format!("{} {}", package.name, package.version),
+                            package_reference_line(&project.path, &package.name),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    violations
+}
+
+fn read_config(path: &Path) -> Config {
+    let file = std::fs::File::open(path).unwrap_or_else(|e| {
+        panic!(
+            "Failed to open architecture rules {}: {}",
+            path.display(),
+            e
+        )
+    });
+    serde_json::from_reader(std::io::BufReader::new(file)).unwrap_or_else(|e| {
+        panic!(
+            "Failed to parse architecture rules {}: {}",
+            path.display(),
+            e
+        )
+    })
+}
+
+/// Finds the line of the first element in the project file that satisfies the predicate.
+fn find_line<F>(csproj_path: &Path, predicate: F) -> Option<u32>
+where
+    F: Fn(roxmltree::Node) -> bool,
+{
+    let contents = std::fs::read_to_string(csproj_path).ok()?;
+    let document = roxmltree::Document::parse(&contents).ok()?;
+    let node = document.descendants().find(|&node| predicate(node))?;
+    Some(document.text_pos_at(node.range().start).row)
+}
+
+fn project_reference_line(csproj_path: &Path, to_path: &Path) -> Option<u32> {
+    let csproj_dir = csproj_path.parent().unwrap();
+    find_line(csproj_path, |node| {
+        node.tag_name().name() == "ProjectReference"
+            && node
+                .attribute("Include")
+                .and_then(|include| {
+                    std::fs::canonicalize(csproj_dir.join(native_separators(include)).simplify())
+                        .ok()
+                })
+                .as_deref()
+                == Some(to_path)
+    })
+}
+
+fn package_reference_line(csproj_path: &Path, name: &str) -> Option<u32> {
+    find_line(csproj_path, |node| {
+        node.tag_name().name() == "PackageReference" && node.attribute("Include") == Some(name)
+    })
+}
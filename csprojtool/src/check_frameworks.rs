@@ -0,0 +1,124 @@
+use serde::Serialize;
+
+use crate::discovery::*;
+use crate::path_extensions::*;
+use crate::utils::OutputFormat;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options<'a> {
+    pub search_path: &'a Path,
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub incompatibilities: Vec<Incompatibility>,
+}
+
+/// A project reference where some target frameworks of the project can not consume the referenced project.
+#[derive(Debug, Serialize)]
+pub struct Incompatibility {
+    pub project: PathBuf,
+    pub project_frameworks: Vec<String>,
+    pub reference: PathBuf,
+    pub reference_frameworks: Vec<String>,
+    /// The frameworks to add to the referenced project to make the reference work.
+    pub suggested_frameworks: Vec<String>,
+}
+
+pub fn run(options: Options) {
+    let report = check_frameworks(options);
+
+    match options.format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(std::io::stdout(), &report).unwrap();
+            println!();
+        }
+        OutputFormat::Table => print_table(&report),
+    }
+
+    if !report.incompatibilities.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+/// Checks the project references of the projects under the search path against the target framework
+/// compatibility rules.
+pub fn check_frameworks(options: Options) -> Report {
+    let Options { search_path, .. } = options;
+
+    let current_dir = std::env::current_dir().unwrap();
+    let Root {
+        root_dir,
+        search_path,
+    } = root_dir(search_path).unwrap();
+
+    let mut projects = find_projects(&root_dir);
+    projects.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let incompatibilities = project_edges(&projects, &root_dir)
+        .into_iter()
+        .filter_map(|(from, to)| {
+            let project = &projects[from];
+            let reference = &projects[to];
+
+            // Build only references do not consume the output of the referenced project.
+            if !project.path.starts_with(&search_path)
+                || project
+                    .build_only_project_references
+                    .contains(&reference.path)
+            {
+                return None;
+            }
+
+            let suggested_frameworks = crate::tfm::incompatible_frameworks(
+                &project.target_frameworks,
+                &reference.target_frameworks,
+            );
+            if suggested_frameworks.is_empty() {
+                return None;
+            }
+
+            Some(Incompatibility {
+                project: relative_path(&current_dir, &project.path),
+                project_frameworks: project.target_frameworks.clone(),
+                reference: relative_path(&current_dir, &reference.path),
+                reference_frameworks: reference.target_frameworks.clone(),
+                suggested_frameworks: suggested_frameworks
+                    .into_iter()
+                    .map(str::to_owned)
+                    .collect(),
+            })
+        })
+        .collect();
+
+    Report { incompatibilities }
+}
+
+fn print_table(report: &Report) {
+    if report.incompatibilities.is_empty() {
+        println!("All project references have compatible target frameworks.");
+        return;
+    }
+
+    println!(
+        "Found {} incompatible project reference(s):",
+        report.incompatibilities.len()
+    );
+    for incompatibility in report.incompatibilities.iter() {
+        println!(
+            "  {} [{}] -> {} [{}]",
+            incompatibility.project.display(),
+            incompatibility.project_frameworks.join(", "),
+            incompatibility.reference.display(),
+            incompatibility.reference_frameworks.join(", "),
+        );
+        println!(
+            "    add {} to {}",
+            incompatibility.suggested_frameworks.join(", "),
+            incompatibility.reference.display()
+        );
+    }
+}
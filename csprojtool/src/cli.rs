@@ -1,19 +1,66 @@
 use clap::*;
 
+pub const ARG_BASE: &'static str = "base";
+pub const ARG_BUILD_DEPENDENCIES: &'static str = "build-dependencies";
 pub const ARG_CLEAN_APP_CONFIG: &'static str = "clean-app-config";
+pub const ARG_CLUSTERS: &'static str = "clusters";
+pub const ARG_COLLAPSE: &'static str = "collapse";
+pub const ARG_CONFIG: &'static str = "config";
+pub const ARG_DEPTH: &'static str = "depth";
 pub const ARG_DOT: &'static str = "dot";
+pub const ARG_EGO: &'static str = "ego";
+pub const ARG_EXCLUDE: &'static str = "exclude";
+pub const ARG_EXES_ONLY: &'static str = "exes-only";
+pub const ARG_FAIL_ON_CYCLE: &'static str = "fail-on-cycle";
+pub const ARG_FIX: &'static str = "fix";
+pub const ARG_FORMAT: &'static str = "format";
+pub const ARG_DRY_RUN: &'static str = "dry-run";
 pub const ARG_EXCLUDE_SDK: &'static str = "exclude-sdk";
 pub const ARG_DO_NOT_FOLLOW_OUTGOING_PROJECT_REFERENCES: &'static str = "no-follow";
 pub const ARG_DO_NOT_FOLLOW_INCOMING_PROJECT_REFERENCES: &'static str = "no-follow-incoming";
+pub const ARG_FROM: &'static str = "from";
 pub const ARG_GLOB: &'static str = "glob";
+pub const ARG_KEEP: &'static str = "keep";
+pub const ARG_KEEP_GUIDS: &'static str = "keep-guids";
+pub const ARG_GRAPHML: &'static str = "graphml";
+pub const ARG_HTML: &'static str = "html";
 pub const ARG_JSON: &'static str = "json";
 pub const ARG_SEARCH_PATH: &'static str = "search";
+pub const ARG_LIMIT: &'static str = "limit";
+pub const ARG_MERMAID: &'static str = "mermaid";
+pub const ARG_NAME_TEMPLATE: &'static str = "name-template";
+pub const ARG_ONLY_INCOMING: &'static str = "only-incoming";
+pub const ARG_ONLY_OUTGOING: &'static str = "only-outgoing";
+pub const ARG_OUT_DIR: &'static str = "out-dir";
+pub const ARG_PACKAGES: &'static str = "packages";
+pub const ARG_PACKAGE_FAMILIES: &'static str = "package-families";
+pub const ARG_REV_A: &'static str = "rev-a";
+pub const ARG_REV_B: &'static str = "rev-b";
+pub const ARG_PLANTUML: &'static str = "plantuml";
 pub const ARG_SLN_PATH: &'static str = "sln-file-path";
+pub const ARG_TESTS_ONLY: &'static str = "tests-only";
+pub const ARG_TO: &'static str = "to";
+pub const ARG_TOP: &'static str = "top";
+pub const ARG_VERSION: &'static str = "version";
+pub const ARG_VS_VERSION: &'static str = "vs-version";
+pub const CMD_AFFECTED: &'static str = "affected";
+pub const CMD_BUILD_ORDER: &'static str = "build-order";
+pub const CMD_CHECK_ARCHITECTURE: &'static str = "check-architecture";
+pub const CMD_CHECK_FRAMEWORKS: &'static str = "check-frameworks";
+pub const CMD_CYCLES: &'static str = "cycles";
 pub const CMD_DEPENDENCY_GRAPH: &'static str = "dependency-graph";
+pub const CMD_GRAPH_DIFF: &'static str = "graph-diff";
+pub const CMD_GUIDS: &'static str = "guids";
 pub const CMD_LIST_PROJECTS: &'static str = "list-projects";
 pub const CMD_LIST: &'static str = "list";
+pub const CMD_METRICS: &'static str = "metrics";
+pub const CMD_PACKAGE_CONFLICTS: &'static str = "package-conflicts";
 pub const CMD_POST_MIGRATION_CLEANUP: &'static str = "post-migration-cleanup";
+pub const CMD_PRUNE_REFERENCES: &'static str = "prune-references";
 pub const CMD_SLN: &'static str = "sln";
+pub const CMD_SLN_COVERAGE: &'static str = "sln-coverage";
+pub const CMD_SLN_PER_EXE: &'static str = "sln-per-exe";
+pub const CMD_WHY: &'static str = "why";
 
 #[cfg(windows)]
 const DEFAULT_GLOB: &'static str = "**\\*.csproj";
@@ -58,6 +105,35 @@ pub fn build_cli() -> App<'static, 'static> {
         .takes_value(false)
         .help("Clean up app.config files");
 
+    let arg_format = &Arg::with_name(ARG_FORMAT)
+        .long("format")
+        .value_name("FORMAT")
+        .possible_values(&["table", "json"])
+        .default_value("table")
+        .help("Sets the output format");
+
+    let arg_keep_guids = &Arg::with_name(ARG_KEEP_GUIDS)
+        .long("keep-guids")
+        .takes_value(false)
+        .help("Reuse the folder and project guids of an existing solution file");
+
+    let arg_build_dependencies = &Arg::with_name(ARG_BUILD_DEPENDENCIES)
+        .long("build-dependencies")
+        .value_name("JSON_PATH")
+        .help("Json file mapping project paths to the project paths they must be built after");
+
+    let arg_vs_version = &Arg::with_name(ARG_VS_VERSION)
+        .long("vs-version")
+        .value_name("VERSION")
+        .help(
+            "Sets the VisualStudioVersion, defaults to the version in the existing solution file",
+        );
+
+    let arg_fail_on_cycle = &Arg::with_name(ARG_FAIL_ON_CYCLE)
+        .long("fail-on-cycle")
+        .takes_value(false)
+        .help("Exit with a non-zero code when a project reference cycle is found");
+
     let exclude_sdk = &Arg::with_name(ARG_EXCLUDE_SDK).long("exclude-sdk");
 
     App::new("csprojtool")
@@ -76,11 +152,97 @@ pub fn build_cli() -> App<'static, 'static> {
                         .value_name("DOT_PATH")
                         .help("Writes the output to a dot file"),
                 )
+                .arg(
+                    Arg::with_name(ARG_CLUSTERS)
+                        .long("clusters")
+                        .takes_value(false)
+                        .requires(ARG_DOT)
+                        .help("Groups the projects in the dot output in a cluster per directory"),
+                )
+                .arg(
+                    Arg::with_name(ARG_COLLAPSE)
+                        .long("collapse")
+                        .value_name("DEPTH")
+                        .requires(ARG_DOT)
+                        .conflicts_with(ARG_CLUSTERS)
+                        .help("Collapses the projects in the dot output into a node per directory at the given depth"),
+                )
                 .arg(
                     Arg::with_name(ARG_JSON)
                         .long("json")
                         .value_name("JSON_PATH")
-                        .help("Writes the output to a json file"),
+                        .help("Writes the graph to a json file, see the README for its schema"),
+                )
+                .arg(
+                    Arg::with_name(ARG_MERMAID)
+                        .long("mermaid")
+                        .value_name("MERMAID_PATH")
+                        .help("Writes the output to a mermaid flowchart file"),
+                )
+                .arg(
+                    Arg::with_name(ARG_GRAPHML)
+                        .long("graphml")
+                        .value_name("GRAPHML_PATH")
+                        .help("Writes the output to a graphml file"),
+                )
+                .arg(
+                    Arg::with_name(ARG_PLANTUML)
+                        .long("plantuml")
+                        .value_name("PLANTUML_PATH")
+                        .help("Writes the output to a plantuml component diagram file"),
+                )
+                .arg(
+                    Arg::with_name(ARG_HTML)
+                        .long("html")
+                        .value_name("HTML_PATH")
+                        .help("Writes the output to a self-contained interactive html file"),
+                )
+                .arg(
+                    Arg::with_name(ARG_PACKAGES)
+                        .long("packages")
+                        .takes_value(false)
+                        .help("Adds package references as nodes, highlighting version conflicts"),
+                )
+                .arg(
+                    Arg::with_name(ARG_PACKAGE_FAMILIES)
+                        .long("package-families")
+                        .value_name("SEGMENTS")
+                        .requires(ARG_PACKAGES)
+                        .help("Groups packages by the given number of leading name segments"),
+                )
+                .arg(arg_fail_on_cycle)
+                .arg(
+                    Arg::with_name(ARG_EXCLUDE)
+                        .long("exclude")
+                        .value_name("GLOB")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Leaves out projects matching the glob, relative to the search directory, without following their references"),
+                )
+                .arg(
+                    Arg::with_name(ARG_DEPTH)
+                        .long("depth")
+                        .value_name("N")
+                        .help("Only includes projects within N references of the projects matching the glob"),
+                )
+                .arg(
+                    Arg::with_name(ARG_EGO)
+                        .long("ego")
+                        .value_name("PROJECT_PATH")
+                        .help("Shows the projects referencing and referenced by this project instead of the glob matches"),
+                )
+                .arg(
+                    Arg::with_name(ARG_ONLY_INCOMING)
+                        .long("only-incoming")
+                        .takes_value(false)
+                        .conflicts_with(ARG_ONLY_OUTGOING)
+                        .help("Only follows references to the seed projects, showing the projects that depend on them"),
+                )
+                .arg(
+                    Arg::with_name(ARG_ONLY_OUTGOING)
+                        .long("only-outgoing")
+                        .takes_value(false)
+                        .help("Only follows references from the seed projects, the default without --ego"),
                 ),
             clap::SubCommand::with_name(CMD_POST_MIGRATION_CLEANUP)
                 .about("Perform post csproj migration cleanup")
@@ -93,6 +255,12 @@ pub fn build_cli() -> App<'static, 'static> {
                 .arg(arg_search)
                 .arg(arg_do_not_follow_outgoing_project_references)
                 .arg(arg_do_not_follow_incoming_project_references),
+            clap::SubCommand::with_name(CMD_BUILD_ORDER)
+                .about("List projects in build order, grouped into layers that can be built in parallel")
+                .arg(arg_search)
+                .arg(arg_do_not_follow_outgoing_project_references)
+                .arg(arg_do_not_follow_incoming_project_references)
+                .arg(arg_format),
             clap::SubCommand::with_name(CMD_SLN)
                 .about("Generate a solution file")
                 .arg(
@@ -103,7 +271,205 @@ pub fn build_cli() -> App<'static, 'static> {
                 )
                 .arg(arg_search)
                 .arg(arg_do_not_follow_outgoing_project_references)
-                .arg(arg_do_not_follow_incoming_project_references),
+                .arg(arg_do_not_follow_incoming_project_references)
+                .arg(arg_keep_guids)
+                .arg(arg_build_dependencies)
+                .arg(arg_vs_version),
+            clap::SubCommand::with_name(CMD_SLN_PER_EXE)
+                .about("Generate a solution file for every executable project")
+                .arg(arg_search)
+                .arg(
+                    Arg::with_name(ARG_OUT_DIR)
+                        .long("out-dir")
+                        .value_name("OUT_DIR")
+                        .help("Directory to write the solution files to")
+                        .default_value("."),
+                )
+                .arg(
+                    Arg::with_name(ARG_NAME_TEMPLATE)
+                        .long("name-template")
+                        .value_name("TEMPLATE")
+                        .help("Solution file name, {name} is replaced with the executable project name")
+                        .default_value("{name}.sln"),
+                )
+                .arg(arg_keep_guids)
+                .arg(arg_build_dependencies)
+                .arg(arg_vs_version),
+            clap::SubCommand::with_name(CMD_SLN_COVERAGE)
+                .about("Report which projects are included in which solution files")
+                .arg(arg_search)
+                .arg(arg_format),
+            clap::SubCommand::with_name(CMD_CYCLES)
+                .about("Report project reference cycles")
+                .arg(arg_search)
+                .arg(arg_format)
+                .arg(arg_fail_on_cycle),
+            clap::SubCommand::with_name(CMD_PRUNE_REFERENCES)
+                .about("Remove project references that are already implied transitively")
+                .arg(arg_search)
+                .arg(
+                    Arg::with_name(ARG_DRY_RUN)
+                        .long("dry-run")
+                        .takes_value(false)
+                        .help("Only list the references that would be removed"),
+                )
+                .arg(
+                    Arg::with_name(ARG_KEEP)
+                        .long("keep")
+                        .value_name("GLOB")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Never remove references to projects matching the glob, relative to the root directory"),
+                ),
+            clap::SubCommand::with_name(CMD_GUIDS)
+                .about("Report duplicate and missing project guids")
+                .arg(arg_search)
+                .arg(
+                    Arg::with_name(ARG_FIX)
+                        .long("fix")
+                        .takes_value(false)
+                        .help("Assign new guids and update project references and solution files"),
+                ),
+            clap::SubCommand::with_name(CMD_PACKAGE_CONFLICTS)
+                .about("Report packages referenced with more than one version, directly or within the closure of an executable")
+                .arg(arg_search)
+                .arg(
+                    Arg::with_name(ARG_FIX)
+                        .long("fix")
+                        .takes_value(false)
+                        .help("Align the package references to the highest version"),
+                )
+                .arg(
+                    Arg::with_name(ARG_VERSION)
+                        .long("version")
+                        .value_name("PACKAGE=VERSION")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .requires(ARG_FIX)
+                        .help("Align the package to this version instead of the highest one"),
+                )
+                .arg(arg_format),
+            clap::SubCommand::with_name(CMD_AFFECTED)
+                .about("List the projects affected by the changes since a git revision")
+                .arg(arg_search)
+                .arg(
+                    Arg::with_name(ARG_BASE)
+                        .long("base")
+                        .value_name("REVISION")
+                        .required(true)
+                        .help("The revision to compare against, e.g. origin/main"),
+                )
+                .arg(
+                    Arg::with_name(ARG_TESTS_ONLY)
+                        .long("tests-only")
+                        .takes_value(false)
+                        .conflicts_with(ARG_EXES_ONLY)
+                        .help("Only list test projects"),
+                )
+                .arg(
+                    Arg::with_name(ARG_EXES_ONLY)
+                        .long("exes-only")
+                        .takes_value(false)
+                        .help("Only list executable projects"),
+                )
+                .arg(arg_format),
+            clap::SubCommand::with_name(CMD_CHECK_ARCHITECTURE)
+                .about("Check project and package references against architecture rules")
+                .arg(
+                    Arg::with_name(ARG_CONFIG)
+                        .long("config")
+                        .value_name("JSON_PATH")
+                        .required(true)
+                        .help("Json file with the rules, globs are relative to its directory"),
+                )
+                .arg(arg_search)
+                .arg(arg_format),
+            clap::SubCommand::with_name(CMD_CHECK_FRAMEWORKS)
+                .about("Check that every project reference targets a framework the referencing project can consume")
+                .arg(arg_search)
+                .arg(arg_format),
+            clap::SubCommand::with_name(CMD_METRICS)
+                .about("Report fan-in, fan-out, closure size, depth and instability of projects")
+                .arg(arg_search)
+                .arg(arg_glob)
+                .arg(
+                    Arg::with_name(ARG_TOP)
+                        .long("top")
+                        .value_name("N")
+                        .default_value("10")
+                        .validator(validate_number)
+                        .help("The number of most referenced projects to report as hubs"),
+                )
+                .arg(arg_format),
+            clap::SubCommand::with_name(CMD_GRAPH_DIFF)
+                .about("Compare the projects, references, packages and frameworks between two git revisions")
+                .arg(
+                    Arg::with_name(ARG_REV_A)
+                        .required(true)
+                        .value_name("REV_A")
+                        .help("The revision to compare from"),
+                )
+                .arg(
+                    Arg::with_name(ARG_REV_B)
+                        .required(true)
+                        .value_name("REV_B")
+                        .help("The revision to compare to"),
+                )
+                .arg(arg_search)
+                .arg(
+                    Arg::with_name(ARG_DOT)
+                        .long("dot")
+                        .value_name("DOT_PATH")
+                        .help("Writes the combined graph with added references in green and removed ones in red to a dot file"),
+                )
+                .arg(arg_format),
+            clap::SubCommand::with_name(CMD_WHY)
+                .about("Explain through which project references one project reaches another project or package")
+                .arg(
+                    Arg::with_name(ARG_FROM)
+                        .required(true)
+                        .value_name("FROM")
+                        .help("Path to the referencing project"),
+                )
+                .arg(
+                    Arg::with_name(ARG_TO)
+                        .required(true)
+                        .value_name("TO")
+                        .help("Path to the referenced project, or the name of a package"),
+                )
+                .arg(
+                    Arg::with_name(ARG_LIMIT)
+                        .long("limit")
+                        .value_name("K")
+                        .validator(validate_limit)
+                        .help("Only print the K shortest paths, all paths are printed without a limit or with `all` or 0"),
+                )
+                .arg(arg_format),
             crate::move_command::MoveCommand::subcommand(),
         ])
 }
+
+fn validate_number(value: String) -> std::result::Result<(), String> {
+    value
+        .parse::<usize>()
+        .map(|_| ())
+        .map_err(|_| format!("expected a number, found `{}`", value))
+}
+
+fn validate_limit(value: String) -> std::result::Result<(), String> {
+    if value == "all" {
+        return Ok(());
+    }
+    value
+        .parse::<usize>()
+        .map(|_| ())
+        .map_err(|_| format!("expected a number or `all`, found `{}`", value))
+}
+
+/// Parses the `--limit` of `why`, `usize::MAX` stands for all paths.
+pub fn parse_limit(value: Option<&str>) -> usize {
+    match value {
+        None | Some("all") | Some("0") => usize::MAX,
+        Some(value) => value.parse().unwrap(),
+    }
+}
@@ -0,0 +1,197 @@
+use log::warn;
+use serde::Serialize;
+
+use crate::path_extensions::*;
+use crate::sln::parse::ParsedSolution;
+use crate::utils::OutputFormat;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options<'a> {
+    pub search_path: &'a Path,
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub solutions: Vec<PathBuf>,
+    pub projects: Vec<ProjectCoverage>,
+    pub orphans: Vec<PathBuf>,
+    pub dangling: Vec<DanglingEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectCoverage {
+    pub path: PathBuf,
+    /// Indices into `Report::solutions`.
+    pub solutions: Vec<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DanglingEntry {
+    pub solution: PathBuf,
+    pub name: String,
+    pub path: String,
+    pub guid: Uuid,
+}
+
+pub fn run(options: Options) {
+    let report = coverage(options);
+
+    match options.format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(std::io::stdout(), &report).unwrap();
+            println!();
+        }
+        OutputFormat::Table => print_table(&report),
+    }
+}
+
+pub fn coverage(options: Options) -> Report {
+    let Options { search_path, .. } = options;
+
+    let current_dir = std::env::current_dir().unwrap();
+    let root_dir = crate::discovery::root_dir(search_path).unwrap().root_dir;
+
+    let projects = crate::list::list(crate::list::Options {
+        search_path: &root_dir,
+        follow_incoming_project_references: false,
+        follow_outgoing_project_references: false,
+    });
+
+    let mut project_paths = projects
+        .into_iter()
+        .map(|project| project.path)
+        .collect::<Vec<_>>();
+    project_paths.sort();
+
+    let path_to_project_index = project_paths
+        .iter()
+        .enumerate()
+        .map(|(index, path)| (path.clone(), index))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut solution_paths = crate::sln::find_solutions(&root_dir);
+    solution_paths.sort();
+
+    let mut project_solutions = vec![Vec::new(); project_paths.len()];
+    let mut dangling = Vec::new();
+
+    for (solution_index, solution_path) in solution_paths.iter().enumerate() {
+        let solution = match ParsedSolution::read(solution_path) {
+            Ok(solution) => solution,
+            Err(e) => {
+                warn!(
+                    "Ignoring solution at {} due to read failure: {}",
+                    solution_path.display(),
+                    e
+                );
+                continue;
+            }
+        };
+
+        for entry in solution.projects() {
+            match std::fs::canonicalize(solution.entry_path(entry)) {
+                Ok(path) => {
+                    if let Some(&project_index) = path_to_project_index.get(&path) {
+                        let solutions = &mut project_solutions[project_index];
+                        if solutions.last() != Some(&solution_index) {
+                            solutions.push(solution_index);
+                        }
+                    }
+                }
+                Err(_) => dangling.push(DanglingEntry {
+                    solution: relative_path(&current_dir, solution_path),
+                    name: entry.name.clone(),
+                    path: entry.path.clone(),
+                    guid: entry.guid,
+                }),
+            }
+        }
+    }
+
+    let projects = project_paths
+        .iter()
+        .zip(project_solutions)
+        .map(|(path, solutions)| ProjectCoverage {
+            path: relative_path(&current_dir, path),
+            solutions,
+        })
+        .collect::<Vec<_>>();
+
+    let orphans = projects
+        .iter()
+        .filter(|project| project.solutions.is_empty())
+        .map(|project| project.path.clone())
+        .collect();
+
+    Report {
+        solutions: solution_paths
+            .iter()
+            .map(|path| relative_path(&current_dir, path))
+            .collect(),
+        projects,
+        orphans,
+        dangling,
+    }
+}
+
+fn print_table(report: &Report) {
+    println!("Solutions:");
+    for (index, path) in report.solutions.iter().enumerate() {
+        println!("  [{}] {}", index, path.display());
+    }
+
+    let width = report
+        .projects
+        .iter()
+        .map(|project| project.path.display().to_string().len())
+        .max()
+        .unwrap_or_default();
+    let column_width = report.solutions.len().to_string().len() + 1;
+
+    println!();
+    print!("  {:width$}", "", width = width);
+    for index in 0..report.solutions.len() {
+        print!(" {:>column_width$}", index, column_width = column_width);
+    }
+    println!("  total");
+
+    for project in report.projects.iter() {
+        print!(
+            "  {:width$}",
+            project.path.display().to_string(),
+            width = width
+        );
+        for index in 0..report.solutions.len() {
+            let mark = if project.solutions.contains(&index) {
+                "x"
+            } else {
+                "."
+            };
+            print!(" {:>column_width$}", mark, column_width = column_width);
+        }
+        println!("  {}", project.solutions.len());
+    }
+
+    println!();
+    println!("Orphan projects ({}):", report.orphans.len());
+    for path in report.orphans.iter() {
+        println!("  {}", path.display());
+    }
+
+    println!();
+    println!("Dangling solution entries ({}):", report.dangling.len());
+    for entry in report.dangling.iter() {
+        println!(
+            "  {}: {} ({}) {{{:X}}}",
+            entry.solution.display(),
+            entry.name,
+            entry.path,
+            entry.guid
+        );
+    }
+}
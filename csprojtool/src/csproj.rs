@@ -3,31 +3,9 @@ use lazy_static::lazy_static;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::fmt;
-use std::path::Path;
-use std::{collections::HashMap, path::PathBuf};
+use std::path::PathBuf;
 use uuid::Uuid;
 
-pub fn search_for_projects(glob_pattern: &str) -> HashMap<PathBuf, Option<Result<Project, Error>>> {
-    glob::glob(glob_pattern)
-        .unwrap()
-        .filter_map(|project_path| {
-            let project_path = project_path.unwrap();
-            let project_path = std::fs::canonicalize(project_path).unwrap();
-            let meta = std::fs::metadata(&project_path).unwrap();
-            if !meta.is_file() {
-                return None;
-            }
-
-            Some((project_path, None))
-        })
-        .collect()
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct JsonRoot {
-    pub projects: Vec<Project>,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
     pub path: PathBuf,
@@ -36,7 +14,28 @@ pub struct Project {
     pub project_guid: Option<Uuid>,
     pub target_frameworks: Vec<String>,
     pub project_references: Vec<PathBuf>,
+    /// Project references that only affect the build order because they do not reference the output assembly.
+    pub build_only_project_references: Vec<PathBuf>,
+    /// Project references with `PrivateAssets` set to `all`, the referenced project does not flow to consumers.
+    pub private_project_references: Vec<PathBuf>,
+    /// Set through the `DisableTransitiveProjectReferences` property.
+    pub disable_transitive_project_references: bool,
     pub package_references: Vec<PackageReference>,
+    /// Explicitly imported props and targets files, imports using MSBuild properties are left out.
+    pub imports: Vec<PathBuf>,
+}
+
+impl Project {
+    /// Test projects either reference the test sdk or follow the `*.Tests` naming convention.
+    pub fn is_test(&self) -> bool {
+        self.package_references
+            .iter()
+            .any(|package| package.name == "Microsoft.NET.Test.Sdk")
+            || match self.path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(stem) => stem.ends_with(".Tests") || stem.ends_with(".Test"),
+                None => false,
+            }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -108,78 +107,26 @@ impl std::error::Error for Error {
     }
 }
 
-pub fn parse_projects(
-    search_path: &Path,
-    glob_matcher: &globset::GlobMatcher,
-    follow_project_references: bool,
-) -> HashMap<PathBuf, Result<Project, Error>> {
-    let meta = std::fs::metadata(search_path).unwrap();
-    let mut todo: Vec<PathBuf> = if meta.is_file() {
-        vec![search_path.to_path_buf()]
-    } else {
-        find_files(search_path, glob_matcher).collect()
-    };
-
-    let mut projects: HashMap<PathBuf, Option<Result<Project, Error>>> = todo
-        .iter()
-        .map(|project_path| (project_path.clone(), None))
-        .collect();
-
-    let mut new_todo = vec![];
-
-    while !todo.is_empty() {
-        for project_path in todo.drain(..) {
-            let result = read_and_parse_project(project_path.clone());
-
-            if let Ok(project) = &result {
-                if follow_project_references {
-                    for project_path in project.project_references.iter() {
-                        if !projects.contains_key(project_path) {
-                            projects.insert(project_path.clone(), None);
-                            new_todo.push(project_path.clone());
-                        }
-                    }
-                }
-            }
-
-            assert!(projects
-                .get_mut(&project_path)
-                .unwrap()
-                .replace(result)
-                .is_none());
-        }
+pub fn read_and_parse_project(project_path: PathBuf) -> Result<Project, Error> {
+    let contents = std::fs::read_to_string(&project_path)?;
 
-        todo.clear();
-        std::mem::swap(&mut todo, &mut new_todo);
+    let mut project = parse_project(project_path, &contents)?;
+    for ref_path in project
+        .project_references
+        .iter_mut()
+        .chain(project.build_only_project_references.iter_mut())
+        .chain(project.private_project_references.iter_mut())
+    {
+        *ref_path = std::fs::canonicalize(&ref_path)?;
     }
 
-    projects.into_iter().map(|(k, v)| (k, v.unwrap())).collect()
+    Ok(project)
 }
 
-fn find_files<'a>(
-    search_path: &Path,
-    glob_matcher: &'a globset::GlobMatcher,
-) -> impl Iterator<Item = PathBuf> + 'a {
-    let walk_builder = ignore::WalkBuilder::new(search_path);
-    let cwd = std::fs::canonicalize(std::env::current_dir().unwrap()).unwrap();
-    walk_builder
-        .build()
-        .filter_map(move |result| -> Option<PathBuf> {
-            let entry = result.unwrap();
-            let meta = entry.metadata().unwrap();
-            let path = std::fs::canonicalize(entry.path()).unwrap();
-            let rel_path = relative_path(cwd.as_path(), path.as_path());
-            if meta.is_file() && glob_matcher.is_match(rel_path) {
-                Some(path)
-            } else {
-                None
-            }
-        })
-}
-pub fn read_and_parse_project(project_path: PathBuf) -> Result<Project, Error> {
-    let contents = std::fs::read_to_string(&project_path)?;
-
-    let document = roxmltree::Document::parse(&contents)?;
+/// Parses the contents of a project file without touching the file system, so reference and import paths are only
+/// simplified and not canonicalized.
+pub fn parse_project(project_path: PathBuf, contents: &str) -> Result<Project, Error> {
+    let document = roxmltree::Document::parse(contents)?;
 
     let project_dir = project_path
         .parent()
@@ -260,17 +207,46 @@ pub fn read_and_parse_project(project_path: PathBuf) -> Result<Project, Error> {
         target_frameworks
     };
 
-    let project_references = document
-        .descendants()
-        .filter_map(|node| -> Option<std::io::Result<PathBuf>> {
-            if node.tag_name().name() != "ProjectReference" {
-                return None;
-            }
-            let ref_path = PathBuf::from(node.attribute("Include")?);
-            let ref_path = project_dir.join(&ref_path).simplify();
-            Some(std::fs::canonicalize(ref_path))
-        })
-        .collect::<Result<Vec<PathBuf>, std::io::Error>>()?;
+    let disable_transitive_project_references = document.descendants().any(|node| {
+        node.tag_name().name() == "DisableTransitiveProjectReferences"
+            && matches!(node.text(), Some(value) if value.trim().eq_ignore_ascii_case("true"))
+    });
+
+    let mut project_references = Vec::new();
+    let mut build_only_project_references = Vec::new();
+    let mut private_project_references = Vec::new();
+    for node in document.descendants() {
+        if node.tag_name().name() != "ProjectReference" {
+            continue;
+        }
+        let ref_path = match node.attribute("Include") {
+            Some(include) => PathBuf::from(native_separators(include)),
+            None => continue,
+        };
+        let ref_path = project_dir.join(&ref_path).simplify();
+
+        let reference_output_assembly = node.attribute("ReferenceOutputAssembly").or_else(|| {
+            node.children()
+                .find(|child| child.tag_name().name() == "ReferenceOutputAssembly")
+                .and_then(|child| child.text())
+        });
+        if matches!(reference_output_assembly, Some(value) if value.trim().eq_ignore_ascii_case("false"))
+        {
+            build_only_project_references.push(ref_path.clone());
+        }
+
+        let private_assets = node.attribute("PrivateAssets").or_else(|| {
+            node.children()
+                .find(|child| child.tag_name().name() == "PrivateAssets")
+                .and_then(|child| child.text())
+        });
+        if matches!(private_assets, Some(value) if value.split(';').any(|asset| asset.trim().eq_ignore_ascii_case("all")))
+        {
+            private_project_references.push(ref_path.clone());
+        }
+
+        project_references.push(ref_path);
+    }
 
     let package_references = document
         .descendants()
@@ -280,10 +256,27 @@ pub fn read_and_parse_project(project_path: PathBuf) -> Result<Project, Error> {
             }
             Some(PackageReference {
                 name: node.attribute("Include")?.to_string(),
-                version: node.attribute("Version")?.to_string(),
+                version: node
+                    .attribute("Version")
+                    .or_else(|| {
+                        node.children()
+                            .find(|child| child.tag_name().name() == "Version")
+                            .and_then(|child| child.text())
+                    })?
+                    .trim()
+                    .to_string(),
             })
         })
         .collect::<Vec<_>>();
+
+    let imports = document
+        .descendants()
+        .filter(|node| node.tag_name().name() == "Import")
+        .filter_map(|node| node.attribute("Project"))
+        .filter(|import| !import.contains("$("))
+        .map(|import| project_dir.join(native_separators(import)).simplify())
+        .collect::<Vec<_>>();
+
     Ok(Project {
         path: project_path,
         is_sdk,
@@ -291,7 +284,11 @@ pub fn read_and_parse_project(project_path: PathBuf) -> Result<Project, Error> {
         project_guid,
         target_frameworks,
         project_references,
+        build_only_project_references,
+        private_project_references,
+        disable_transitive_project_references,
         package_references,
+        imports,
     })
 }
 
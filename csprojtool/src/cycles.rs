@@ -0,0 +1,86 @@
+use serde::Serialize;
+
+use crate::discovery::*;
+use crate::path_extensions::*;
+use crate::utils::OutputFormat;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options<'a> {
+    pub search_path: &'a Path,
+    pub format: OutputFormat,
+    /// Exit with a non-zero code when a cycle is found.
+    pub fail_on_cycle: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    /// The paths of the projects in each project reference cycle.
+    pub cycles: Vec<Vec<PathBuf>>,
+}
+
+pub fn run(options: Options) {
+    let report = cycles(options);
+
+    match options.format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(std::io::stdout(), &report).unwrap();
+            println!();
+        }
+        OutputFormat::Table => print_table(&report),
+    }
+
+    if options.fail_on_cycle && !report.cycles.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+/// Finds the project reference cycles that contain at least one project under the search path.
+pub fn cycles(options: Options) -> Report {
+    let Options { search_path, .. } = options;
+
+    let current_dir = std::env::current_dir().unwrap();
+    let Root {
+        root_dir,
+        search_path,
+    } = root_dir(search_path).unwrap();
+
+    let mut projects = find_projects(&root_dir);
+    projects.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let edges = project_edges(&projects, &root_dir);
+    let outgoing = crate::graph::outgoing_edges(projects.len(), &edges);
+
+    let cycles = crate::graph::cycles(&outgoing)
+        .into_iter()
+        .filter(|cycle| {
+            cycle
+                .iter()
+                .any(|&index| projects[index].path.starts_with(&search_path))
+        })
+        .map(|cycle| {
+            cycle
+                .into_iter()
+                .map(|index| relative_path(&current_dir, &projects[index].path))
+                .collect()
+        })
+        .collect();
+
+    Report { cycles }
+}
+
+fn print_table(report: &Report) {
+    if report.cycles.is_empty() {
+        println!("No project reference cycles found.");
+        return;
+    }
+
+    println!("Found {} project reference cycle(s):", report.cycles.len());
+    for (index, cycle) in report.cycles.iter().enumerate() {
+        println!("  [{}] {} projects", index, cycle.len());
+        for path in cycle.iter() {
+            println!("    {}", path.display());
+        }
+    }
+}
@@ -1,252 +1,255 @@
+pub mod dot;
+mod graphml;
+mod html;
+mod json;
+mod mermaid;
+pub mod model;
+mod plantuml;
+
 use crate::csproj::*;
+use crate::discovery::discover;
 use crate::path_extensions::*;
-use std::{collections::HashMap, path::PathBuf};
-
-pub fn dependency_graph(glob: &str, search: &str, dot: Option<&str>, json: Option<&str>) {
-    // if we pass a file path, projects should contain that file
-    // if we pass a directory path, projects should glob that directory
-    // if we don't pass a path, projects should glob the current directory
-
-    let (search_dir, mut projects) = {
-        let search_path =
-            std::fs::canonicalize(search).expect("Failed to canonicalize path, does it exist?");
-        let meta = std::fs::metadata(&search_path).unwrap();
-        if meta.is_file() {
-            let search_dir = search_path.parent().unwrap().to_path_buf();
-            let projects = Some((search_path, None)).into_iter().collect();
-            (search_dir, projects)
-        } else if meta.is_dir() {
-            let search_dir = search_path;
-            let original_current_dir = std::env::current_dir().unwrap();
-            std::env::set_current_dir(&search_dir).unwrap();
-            let projects = search_for_projects(glob);
-            std::env::set_current_dir(original_current_dir).unwrap();
-            (search_dir, projects)
-        } else {
-            panic!("Specified path is not a file nor a directory!")
-        }
-    };
-
-    loop {
-        let todo = projects
-            .iter()
-            .filter_map(|(path, project)| {
-                if project.is_some() {
-                    None
-                } else {
-                    Some(path.clone())
-                }
-            })
-            .collect::<Vec<_>>();
-
-        if todo.is_empty() {
-            break;
-        }
+use model::Graph;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options<'a> {
+    pub glob: &'a str,
+    pub search: &'a str,
+    pub dot: Option<&'a str>,
+    pub dot_options: dot::DotOptions,
+    pub json: Option<&'a str>,
+    pub mermaid: Option<&'a str>,
+    pub graphml: Option<&'a str>,
+    pub plantuml: Option<&'a str>,
+    pub html: Option<&'a str>,
+    /// Add package references as nodes.
+    pub packages: bool,
+    /// Group packages by this number of leading name segments.
+    pub package_family_depth: Option<usize>,
+    /// Exit with a non-zero code when a cycle is found.
+    pub fail_on_cycle: bool,
+    /// Leave out the projects matching these globs, relative to the search directory, and do not follow their
+    /// references.
+    pub exclude: &'a globset::GlobSet,
+    /// Only keep the projects within this many references of the seed projects.
+    pub depth: Option<usize>,
+    /// Use this project as the only seed instead of the projects matching the glob.
+    pub ego: Option<&'a Path>,
+    /// Keep the projects the seeds depend on.
+    pub outgoing: bool,
+    /// Keep the projects that depend on the seeds.
+    pub incoming: bool,
+}
 
-        for project_path in todo {
-            let project = read_and_parse_project(project_path.clone());
+pub fn dependency_graph(options: Options) {
+    let Options {
+        glob,
+        search,
+        dot,
+        dot_options,
+        json,
+        mermaid,
+        graphml,
+        plantuml,
+        html,
+        packages,
+        package_family_depth,
+        fail_on_cycle,
+        exclude,
+        depth,
+        ego,
+        outgoing,
+        incoming,
+    } = options;
+
+    let (search_dir, projects, seeds) = load_seeded_projects(glob, search, exclude, ego);
+    let selected = select_projects(&projects, &seeds, depth, outgoing, incoming);
+    let projects = projects
+        .into_iter()
+        .zip(selected)
+        .filter_map(|(project, selected)| if selected { Some(project) } else { None })
+        .collect::<Vec<_>>();
 
-            if let Ok(project) = &project {
-                for path in project.project_references.iter().cloned() {
-                    projects.entry(path).or_insert(None);
-                }
-            }
+    let mut graph = Graph::new(&projects);
+    if packages {
+        graph.add_packages(&projects, package_family_depth);
 
-            projects.insert(project_path, Some(project));
+        for package in graph.packages.iter().filter(|package| package.conflict) {
+            let versions = graph
+                .package_edges
+                .iter()
+                .filter(|edge| edge.conflict && graph.packages[edge.target].name == package.name)
+                .map(|edge| edge.version.as_str())
+                .collect::<std::collections::BTreeSet<_>>();
+            eprintln!(
+                "Package {} is referenced with conflicting versions {}",
+                package.name,
+                versions.into_iter().collect::<Vec<_>>().join(", ")
+            );
         }
     }
 
-    let mut projects = projects
-        .into_iter()
-        .map(|(project_path, maybe_project)| {
-            let project_path = relative_path(&search_dir, &project_path);
-
-            let mut project = maybe_project.unwrap();
-            if let Ok(project) = project.as_mut() {
-                project.path = relative_path(&search_dir, &project.path);
-                for dependency_path in project.project_references.iter_mut() {
-                    *dependency_path = relative_path(&search_dir, dependency_path);
-                }
+    if !graph.cycles.is_empty() {
+        eprintln!("Found {} project reference cycle(s):", graph.cycles.len());
+        for cycle in graph.cycles.iter() {
+            eprintln!("  cycle of {} project(s):", cycle.len());
+            for &index in cycle.iter() {
+                eprintln!("    {}", projects[index].0.display());
             }
-            (project_path, project)
-        })
-        .collect::<Vec<_>>();
-    projects.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+    }
 
+    type Write = fn(&mut std::io::BufWriter<std::fs::File>, &Graph) -> std::io::Result<()>;
+    let writers: [(Option<&str>, Write); 4] = [
+        (mermaid, mermaid::write),
+        (graphml, graphml::write),
+        (plantuml, plantuml::write),
+        (html, html::write),
+    ];
     if let Some(path) = dot {
         let mut file = std::io::BufWriter::new(std::fs::File::create(path).unwrap());
-        serialize_dot(&mut file, &projects).unwrap();
+        dot::write(&mut file, &graph, dot_options).unwrap();
+    }
+
+    for (path, write) in writers.iter() {
+        if let Some(path) = path {
+            let mut file = std::io::BufWriter::new(std::fs::File::create(path).unwrap());
+            write(&mut file, &graph).unwrap();
+        }
     }
 
     if let Some(path) = json {
         let mut file = std::io::BufWriter::new(std::fs::File::create(path).unwrap());
-        let root = JsonRoot {
-            projects: projects
-                .iter()
-                .filter_map(|(_, project)| project.as_ref().ok().cloned())
-                .collect(),
-        };
-        serde_json::to_writer_pretty(&mut file, &root).unwrap();
+        json::write(&mut file, &graph, &search_dir).unwrap();
     }
-}
 
-fn serialize_dot<W: std::io::Write>(
-    writer: &mut W,
-    projects: &[(PathBuf, Result<Project, Error>)],
-) -> std::io::Result<()> {
-    writeln!(writer, "// {} projects", projects.iter().len())?;
-
-    for (path, project) in projects.iter() {
-        if let Err(e) = project {
-            writeln!(
-                writer,
-                "// failed to read and parse {}: {}",
-                path.display().to_string().replace("\\", "\\\\"),
-                e
-            )?;
-        }
+    if fail_on_cycle && !graph.cycles.is_empty() {
+        std::process::exit(1);
     }
+}
 
-    let nodes = projects
-        .iter()
-        .enumerate()
-        .map(|(index, (path, _))| (path.clone(), index))
-        .collect::<HashMap<PathBuf, usize>>();
-
-    let edges = projects
-        .iter()
-        .enumerate()
-        .map(|(index, (_, project))| {
-            let dependencies = match project {
-                Ok(project) => project
-                    .project_references
-                    .iter()
-                    .map(|path| *nodes.get(path).unwrap())
-                    .collect(),
-                Err(_) => Vec::new(),
-            };
-            (index, dependencies)
-        })
-        .collect::<Vec<(usize, Vec<usize>)>>();
-
-    writeln!(writer, "digraph {{")?;
-    writeln!(writer, "  rankdir = \"LR\";")?;
-
-    for (index, (path, project)) in projects.iter().enumerate() {
-        let path_display = path.display().to_string();
-        let mut parts = path_display.split("\\").peekable();
-        let mut label = String::new();
-        while let Some(part) = parts.next() {
-            let is_last = parts.peek().is_none();
-            if is_last {
-                label.push_str("<B>");
-            }
-            label.push_str(part);
-            if is_last {
-                label.push_str("</B>");
-            } else {
-                label.push_str("<BR/>");
-            }
-        }
-
-        if let Ok(project) = project {
-            label.push_str("<BR/>");
-            label.push_str(&format!("[{}]", project.target_frameworks.join(", ")));
-        }
+/// Project paths together with the result of reading and parsing them.
+pub type Projects = Vec<(PathBuf, Result<Project, Error>)>;
 
-        writeln!(
-            writer,
-            "  n{} [label = < {} >, fillcolor = \"{}\", style = filled, shape = \"{}\"]",
-            index,
-            label,
-            project
-                .as_ref()
-                .map(|project| if project.is_sdk { "#7fc79f" } else { "#fdc086" })
-                .unwrap_or("red"),
-            project
-                .as_ref()
-                .map(|project| if project.is_exe { "box" } else { "ellipse" })
-                .unwrap_or("star")
-        )?;
-    }
+/// Finds the projects matching the glob under the search directory, or the search file itself, together with all the
+/// projects they reference. Paths are made relative to the search directory and sorted.
+pub fn load_projects(glob: &str, search: &str) -> Projects {
+    let (_, projects, seeds) = load_seeded_projects(glob, search, &globset::GlobSet::empty(), None);
+    let selected = select_projects(&projects, &seeds, None, true, false);
+    projects
+        .into_iter()
+        .zip(selected)
+        .filter_map(|(project, selected)| if selected { Some(project) } else { None })
+        .collect()
+}
 
-    // Floyd-warshall our way to a N*N longest path matrix
-    #[allow(non_snake_case)]
-    let N = nodes.len();
-    let mut mat: Vec<_> = std::iter::repeat(0).take(N * N).collect();
+/// Like `load_projects`, but leaves out the excluded projects and also returns the search directory and which
+/// projects are seeds: the ones matching the glob, or only the ego project when given. All other projects under the root directory are loaded
+/// too so the references to the seeds can be followed.
+fn load_seeded_projects(
+    glob: &str,
+    search: &str,
+    exclude: &globset::GlobSet,
+    ego: Option<&Path>,
+) -> (PathBuf, Projects, Vec<bool>) {
+    let glob = globset::Glob::new(glob).unwrap().compile_matcher();
+    let discovery = discover(crate::discovery::Options {
+        search_path: Path::new(search),
+        glob: Some(&glob),
+        follow_incoming_project_references: false,
+        follow_outgoing_project_references: false,
+    })
+    .unwrap_or_else(|e| panic!("Failed to find projects in {}: {}", search, e));
+    let search_dir = discovery.search_dir;
+
+    let seeds = match ego {
+        Some(ego) => {
+            let ego = std::fs::canonicalize(ego)
+                .expect("Failed to canonicalize ego project path, does it exist?");
+            assert!(
+                discovery.projects.iter().any(|(path, _)| *path == ego),
+                "The ego project {} was not found in {}",
+                ego.display(),
+                discovery.root_dir.display()
+            );
+            discovery
+                .projects
+                .iter()
+                .map(|(path, _)| *path == ego)
+                .collect()
+        }
+        None => discovery.seeds,
+    };
 
-    for (i, _) in nodes.iter().enumerate() {
-        mat[i * N + i] = 1;
-    }
+    let projects = discovery
+        .projects
+        .into_iter()
+        .zip(seeds)
+        .filter(|((path, _), _)| !exclude.is_match(relative_path(&search_dir, path)));
 
-    for (source, targets) in edges.iter() {
-        for target in targets {
-            mat[source * N + target] = 2;
-        }
-    }
+    let mut projects = projects
+        .map(|((project_path, mut project), is_seed)| {
+            let project_path = relative_path(&search_dir, &project_path);
 
-    for k in 0..N {
-        for i in 0..N {
-            for j in 0..N {
-                let ik = mat[i * N + k];
-                let kj = mat[k * N + j];
-                let ij = mat[i * N + j];
-                if ik != 0 && kj != 0 && ik + kj - 1 > ij {
-                    mat[i * N + j] = ik + kj - 1
+            if let Ok(project) = project.as_mut() {
+                project.path = relative_path(&search_dir, &project.path);
+                for dependency_path in project
+                    .project_references
+                    .iter_mut()
+                    .chain(project.build_only_project_references.iter_mut())
+                {
+                    *dependency_path = relative_path(&search_dir, dependency_path);
                 }
             }
-        }
-    }
+            ((project_path, project), is_seed)
+        })
+        .collect::<Vec<_>>();
+    projects.sort_by(|a, b| (a.0).0.cmp(&(b.0).0));
+    let (projects, seeds) = projects.into_iter().unzip();
+    (search_dir, projects, seeds)
+}
 
-    for (source, targets) in edges {
-        let sp = projects[source].1.as_ref().ok();
-        for target in targets {
-            let tp = projects[target].1.as_ref().ok();
-
-            let longest_path = mat[source * N + target] - 1;
-            let color = if !compatible_dependency(sp, tp) {
-                Some("#ff0000")
-            } else if longest_path > 1 {
-                Some("#e2e2e2")
-            } else {
-                None
-            };
-
-            writeln!(
-                writer,
-                "  n{} -> n{}{};",
-                source,
-                target,
-                color
-                    .map(|color| format!(" [color = \"{}\"]", color))
-                    .unwrap_or(String::default())
-            )
-            .unwrap();
+/// Selects the seeds and the projects reachable from them within `depth` references, following the references in
+/// the enabled directions.
+fn select_projects(
+    projects: &[(PathBuf, Result<Project, Error>)],
+    seeds: &[bool],
+    depth: Option<usize>,
+    outgoing: bool,
+    incoming: bool,
+) -> Vec<bool> {
+    let outgoing_edges = model::project_edges(projects);
+    let mut incoming_edges = vec![Vec::new(); projects.len()];
+    for (source, targets) in outgoing_edges.iter().enumerate() {
+        for &target in targets.iter() {
+            incoming_edges[target].push(source);
         }
     }
 
-    writeln!(writer, "}}")?;
-
-    Ok(())
-}
-
-fn compatible_dependency(s: Option<&Project>, t: Option<&Project>) -> bool {
-    if let (Some(s), Some(t)) = (s, t) {
-        s.target_frameworks.iter().all(|sf| {
-            let net3 = sf.starts_with("net3");
-            let net4 = sf.starts_with("net4");
-            if net3 || net4 {
-                true
-            } else {
-                // If we're targetting net standard, net core or net5+ we need our dependency to also target one of those.
-                t.target_frameworks.iter().any(|tf| {
-                    let net3 = tf.starts_with("net3");
-                    let net4 = tf.starts_with("net4");
-                    !net3 && !net4
-                })
+    // The directions are followed separately so the dependencies of dependents are not selected.
+    let mut selected = seeds.to_vec();
+    let directions = [(outgoing, &outgoing_edges), (incoming, &incoming_edges)];
+    for (_, edges) in directions.iter().filter(|(enabled, _)| *enabled) {
+        let mut visited = seeds.to_vec();
+        let mut queue = (0..projects.len())
+            .filter(|&index| seeds[index])
+            .map(|index| (index, 0))
+            .collect::<VecDeque<_>>();
+        while let Some((index, distance)) = queue.pop_front() {
+            if matches!(depth, Some(depth) if distance >= depth) {
+                continue;
             }
-        })
-    } else {
-        true
+            for &neighbour in edges[index].iter() {
+                if !visited[neighbour] {
+                    visited[neighbour] = true;
+                    selected[neighbour] = true;
+                    queue.push_back((neighbour, distance + 1));
+                }
+            }
+        }
     }
+    selected
 }
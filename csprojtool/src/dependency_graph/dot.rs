@@ -0,0 +1,303 @@
+use super::model::*;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DotOptions {
+    /// Wrap the projects in a cluster per directory.
+    pub clusters: bool,
+    /// Collapse the projects into a node per directory at this depth, packages are left out.
+    pub collapse_depth: Option<usize>,
+}
+
+pub fn write<W: std::io::Write>(
+    writer: &mut W,
+    graph: &Graph,
+    options: DotOptions,
+) -> std::io::Result<()> {
+    if let Some(depth) = options.collapse_depth {
+        return write_collapsed(writer, graph, depth);
+    }
+
+    writeln!(writer, "// {} projects", graph.nodes.len())?;
+
+    for node in graph.nodes.iter() {
+        if let Some(error) = &node.error {
+            writeln!(
+                writer,
+                "// failed to read and parse {}: {}",
+                node.path.display().to_string().replace("\\", "\\\\"),
+                error
+            )?;
+        }
+    }
+
+    writeln!(writer, "digraph {{")?;
+    writeln!(writer, "  rankdir = \"LR\";")?;
+
+    if options.clusters {
+        let mut root = Directory::default();
+        for (index, node) in graph.nodes.iter().enumerate() {
+            let parts = node.path_parts();
+            let mut directory = &mut root;
+            for part in parts[..parts.len() - 1].iter() {
+                directory = directory.children.entry(part.clone()).or_default();
+            }
+            directory.nodes.push(index);
+        }
+        write_directory(writer, graph, &root, &mut 0, 1)?;
+    } else {
+        for (index, node) in graph.nodes.iter().enumerate() {
+            write_node(writer, index, node, &node.path_parts(), 1)?;
+        }
+    }
+
+    for (family_index, (family, packages)) in graph.package_families().into_iter().enumerate() {
+        let indent = if let Some(family) = family {
+            writeln!(writer, "  subgraph cluster_family_{} {{", family_index)?;
+            writeln!(writer, "    label = \"{}\";", family)?;
+            "    "
+        } else {
+            "  "
+        };
+
+        for index in packages {
+            let package = &graph.packages[index];
+            writeln!(
+                writer,
+                "{}p{} [label = < <B>{}</B> >, fillcolor = \"{}\", style = filled, shape = \"component\"{}]",
+                indent,
+                index,
+                escape(&package.name),
+                PACKAGE_COLOR,
+                if package.conflict {
+                    format!(", color = \"{}\", penwidth = 3", CONFLICT_COLOR)
+                } else {
+                    String::new()
+                }
+            )?;
+        }
+
+        if family.is_some() {
+            writeln!(writer, "  }}")?;
+        }
+    }
+
+    for edge in graph.edges.iter() {
+        writeln!(
+            writer,
+            "  n{} -> n{}{};",
+            edge.source,
+            edge.target,
+            edge.color()
+                .map(|color| format!(" [color = \"{}\"]", color))
+                .unwrap_or_default()
+        )?;
+    }
+
+    for edge in graph.package_edges.iter() {
+        writeln!(
+            writer,
+            "  n{} -> p{} [label = \"{}\", style = dashed{}];",
+            edge.source,
+            edge.target,
+            edge.version,
+            edge.color()
+                .map(|color| format!(", color = \"{}\", fontcolor = \"{}\"", color, color))
+                .unwrap_or_default()
+        )?;
+    }
+
+    writeln!(writer, "}}")?;
+
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+struct Directory {
+    nodes: Vec<usize>,
+    children: BTreeMap<String, Directory>,
+}
+
+fn write_directory<W: std::io::Write>(
+    writer: &mut W,
+    graph: &Graph,
+    directory: &Directory,
+    next_cluster: &mut usize,
+    depth: usize,
+) -> std::io::Result<()> {
+    for &index in directory.nodes.iter() {
+        let node = &graph.nodes[index];
+        let file_name = node.path_parts().pop().unwrap_or_default();
+        write_node(writer, index, node, &[file_name], depth)?;
+    }
+
+    let indent = "  ".repeat(depth);
+    for (name, child) in directory.children.iter() {
+        writeln!(writer, "{}subgraph cluster_dir_{} {{", indent, next_cluster)?;
+        writeln!(
+            writer,
+            "{}  label = \"{}\";",
+            indent,
+            name.replace('"', "\\\"")
+        )?;
+        *next_cluster += 1;
+        write_directory(writer, graph, child, next_cluster, depth + 1)?;
+        writeln!(writer, "{}}}", indent)?;
+    }
+
+    Ok(())
+}
+
+fn write_node<W: std::io::Write>(
+    writer: &mut W,
+    index: usize,
+    node: &Node,
+    parts: &[String],
+    depth: usize,
+) -> std::io::Result<()> {
+    let mut parts = parts.iter().peekable();
+    let mut label = String::new();
+    while let Some(part) = parts.next() {
+        let is_last = parts.peek().is_none();
+        if is_last {
+            label.push_str("<B>");
+        }
+        label.push_str(&escape(part));
+        if is_last {
+            label.push_str("</B>");
+        } else {
+            label.push_str("<BR/>");
+        }
+    }
+
+    if node.kind != NodeKind::Unreadable {
+        label.push_str("<BR/>");
+        label.push_str(&format!("[{}]", node.target_frameworks.join(", ")));
+    }
+
+    writeln!(
+        writer,
+        "{}n{} [label = < {} >, fillcolor = \"{}\", style = filled, shape = \"{}\"{}]",
+        "  ".repeat(depth),
+        index,
+        label,
+        node.fill_color(),
+        match node.kind {
+            NodeKind::Executable => "box",
+            NodeKind::Library => "ellipse",
+            NodeKind::Unreadable => "star",
+        },
+        if node.cycle.is_some() {
+            format!(", color = \"{}\", penwidth = 3", CYCLE_COLOR)
+        } else {
+            String::new()
+        }
+    )?;
+
+    Ok(())
+}
+
+/// Writes a node per directory at the given depth with edges counting the project references between them.
+fn write_collapsed<W: std::io::Write>(
+    writer: &mut W,
+    graph: &Graph,
+    depth: usize,
+) -> std::io::Result<()> {
+    let folder_of = |node: &Node| {
+        let parts = node.path_parts();
+        let dir_parts = &parts[..parts.len() - 1];
+        if dir_parts.is_empty() {
+            ".".to_string()
+        } else {
+            dir_parts[..depth.clamp(1, dir_parts.len())].join("/")
+        }
+    };
+
+    let mut folders = BTreeMap::<String, Vec<usize>>::new();
+    for (index, node) in graph.nodes.iter().enumerate() {
+        folders.entry(folder_of(node)).or_default().push(index);
+    }
+
+    let folder_indices = folders
+        .keys()
+        .enumerate()
+        .map(|(index, folder)| (folder.as_str(), index))
+        .collect::<BTreeMap<_, _>>();
+
+    // Counts the references between folders and whether any of them is incompatible.
+    let mut folder_edges = BTreeMap::<(usize, usize), (usize, bool)>::new();
+    for edge in graph.edges.iter() {
+        let source = folder_indices[folder_of(&graph.nodes[edge.source]).as_str()];
+        let target = folder_indices[folder_of(&graph.nodes[edge.target]).as_str()];
+        if source != target {
+            let entry = folder_edges.entry((source, target)).or_default();
+            entry.0 += 1;
+            entry.1 |= !edge.compatible;
+        }
+    }
+
+    writeln!(
+        writer,
+        "// {} projects in {} folders",
+        graph.nodes.len(),
+        folders.len()
+    )?;
+    writeln!(writer, "digraph {{")?;
+    writeln!(writer, "  rankdir = \"LR\";")?;
+
+    for (index, (folder, nodes)) in folders.iter().enumerate() {
+        let sdk_count = nodes
+            .iter()
+            .filter(|&&node| graph.nodes[node].is_sdk)
+            .count();
+        let fill_color = if sdk_count == nodes.len() {
+            SDK_COLOR
+        } else if sdk_count == 0 {
+            LEGACY_COLOR
+        } else {
+            MIXED_COLOR
+        };
+        let in_cycle = nodes.iter().any(|&node| graph.nodes[node].cycle.is_some());
+
+        writeln!(
+            writer,
+            "  f{} [label = < <B>{}</B><BR/>{} projects >, fillcolor = \"{}\", style = filled, shape = \"folder\"{}]",
+            index,
+            escape(folder),
+            nodes.len(),
+            fill_color,
+            if in_cycle {
+                format!(", color = \"{}\", penwidth = 3", CYCLE_COLOR)
+            } else {
+                String::new()
+            }
+        )?;
+    }
+
+    for ((source, target), (count, incompatible)) in folder_edges {
+        writeln!(
+            writer,
+            "  f{} -> f{} [label = \"{}\", penwidth = {:.1}{}];",
+            source,
+            target,
+            count,
+            1.0 + (count as f64).ln(),
+            if incompatible {
+                format!(", color = \"{}\"", INCOMPATIBLE_COLOR)
+            } else {
+                String::new()
+            }
+        )?;
+    }
+
+    writeln!(writer, "}}")?;
+
+    Ok(())
+}
+
+/// Escapes text for use in an HTML-like label.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,101 @@
+use super::model::*;
+
+pub fn write<W: std::io::Write>(writer: &mut W, graph: &Graph) -> std::io::Result<()> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:schemaLocation="http://graphml.graphdrawing.org/xmlns http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd">"#
+    )?;
+
+    for (id, domain, name, kind) in [
+        ("path", "node", "path", "string"),
+        ("kind", "node", "kind", "string"),
+        ("sdk", "node", "sdk", "boolean"),
+        ("frameworks", "node", "frameworks", "string"),
+        ("error", "node", "error", "string"),
+        ("cycle", "node", "cycle", "int"),
+        ("redundant", "edge", "redundant", "boolean"),
+        ("compatible", "edge", "compatible", "boolean"),
+        ("in_cycle", "edge", "in_cycle", "boolean"),
+        ("name", "node", "name", "string"),
+        ("family", "node", "family", "string"),
+        ("version", "edge", "version", "string"),
+        ("conflict", "all", "conflict", "boolean"),
+    ]
+    .iter()
+    {
+        writeln!(
+            writer,
+            r#"  <key id="{}" for="{}" attr.name="{}" attr.type="{}"/>"#,
+            id, domain, name, kind
+        )?;
+    }
+
+    writeln!(writer, r#"  <graph id="projects" edgedefault="directed">"#)?;
+
+    for (index, node) in graph.nodes.iter().enumerate() {
+        writeln!(writer, r#"    <node id="n{}">"#, index)?;
+        write_data(writer, "path", &node.path.display().to_string())?;
+        write_data(writer, "kind", node.kind.as_str())?;
+        write_data(writer, "sdk", &node.is_sdk.to_string())?;
+        write_data(writer, "frameworks", &node.target_frameworks.join(";"))?;
+        if let Some(error) = &node.error {
+            write_data(writer, "error", error)?;
+        }
+        if let Some(cycle) = node.cycle {
+            write_data(writer, "cycle", &cycle.to_string())?;
+        }
+        writeln!(writer, "    </node>")?;
+    }
+
+    for (index, package) in graph.packages.iter().enumerate() {
+        writeln!(writer, r#"    <node id="p{}">"#, index)?;
+        write_data(writer, "kind", "package")?;
+        write_data(writer, "name", &package.name)?;
+        if let Some(family) = &package.family {
+            write_data(writer, "family", family)?;
+        }
+        write_data(writer, "conflict", &package.conflict.to_string())?;
+        writeln!(writer, "    </node>")?;
+    }
+
+    for (index, edge) in graph.edges.iter().enumerate() {
+        writeln!(
+            writer,
+            r#"    <edge id="e{}" source="n{}" target="n{}">"#,
+            index, edge.source, edge.target
+        )?;
+        write_data(writer, "redundant", &edge.redundant.to_string())?;
+        write_data(writer, "compatible", &edge.compatible.to_string())?;
+        write_data(writer, "in_cycle", &edge.in_cycle.to_string())?;
+        writeln!(writer, "    </edge>")?;
+    }
+
+    for (index, edge) in graph.package_edges.iter().enumerate() {
+        writeln!(
+            writer,
+            r#"    <edge id="pe{}" source="n{}" target="p{}">"#,
+            index, edge.source, edge.target
+        )?;
+        write_data(writer, "version", &edge.version)?;
+        write_data(writer, "conflict", &edge.conflict.to_string())?;
+        writeln!(writer, "    </edge>")?;
+    }
+
+    writeln!(writer, "  </graph>")?;
+    writeln!(writer, "</graphml>")?;
+
+    Ok(())
+}
+
+fn write_data<W: std::io::Write>(writer: &mut W, key: &str, value: &str) -> std::io::Result<()> {
+    writeln!(
+        writer,
+        r#"      <data key="{}">{}</data>"#,
+        key,
+        value
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    )
+}
@@ -0,0 +1,83 @@
+use serde::Serialize;
+
+use super::model::*;
+
+/// The viewer, `/*GRAPH_DATA*/` is replaced with the graph as json.
+const TEMPLATE: &str = include_str!("html_template.html");
+
+#[derive(Serialize)]
+struct HtmlGraph<'a> {
+    nodes: Vec<HtmlNode<'a>>,
+    edges: Vec<HtmlEdge>,
+}
+
+#[derive(Serialize)]
+struct HtmlNode<'a> {
+    path: String,
+    kind: &'static str,
+    sdk: bool,
+    frameworks: &'a [String],
+    packages: Vec<String>,
+    error: Option<&'a str>,
+    cycle: Option<usize>,
+    color: &'static str,
+    /// The length of the longest dependency chain below the node, used as the column in the layout.
+    layer: usize,
+}
+
+#[derive(Serialize)]
+struct HtmlEdge {
+    source: usize,
+    target: usize,
+    color: Option<&'static str>,
+    redundant: bool,
+}
+
+pub fn write<W: std::io::Write>(writer: &mut W, graph: &Graph) -> std::io::Result<()> {
+    let edges = graph
+        .edges
+        .iter()
+        .map(|edge| (edge.source, edge.target))
+        .collect::<Vec<_>>();
+    let layers = crate::graph::layers(&crate::graph::outgoing_edges(graph.nodes.len(), &edges));
+
+    let html_graph = HtmlGraph {
+        nodes: graph
+            .nodes
+            .iter()
+            .zip(layers)
+            .map(|(node, layer)| HtmlNode {
+                path: node.path_parts().join("/"),
+                kind: node.kind.as_str(),
+                sdk: node.is_sdk,
+                frameworks: &node.target_frameworks,
+                packages: node
+                    .package_references
+                    .iter()
+                    .map(|package| format!("{} {}", package.name, package.version))
+                    .collect(),
+                error: node.error.as_deref(),
+                cycle: node.cycle,
+                color: node.fill_color(),
+                layer,
+            })
+            .collect(),
+        edges: graph
+            .edges
+            .iter()
+            .map(|edge| HtmlEdge {
+                source: edge.source,
+                target: edge.target,
+                color: edge.color(),
+                redundant: edge.redundant,
+            })
+            .collect(),
+    };
+
+    // Prevent the data from closing the script element it is embedded in.
+    let data = serde_json::to_string(&html_graph)
+        .unwrap()
+        .replace("</", "<\\/");
+
+    write!(writer, "{}", TEMPLATE.replace("/*GRAPH_DATA*/", &data))
+}
@@ -0,0 +1,97 @@
+use serde::Serialize;
+
+use super::model::*;
+use crate::csproj::PackageReference;
+use std::path::Path;
+
+/// Incremented on every change that can break consumers, the schema is documented in the README.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct JsonGraph<'a> {
+    schema_version: u32,
+    /// The directory the node paths are relative to.
+    root: &'a Path,
+    nodes: Vec<JsonNode<'a>>,
+    edges: Vec<JsonEdge>,
+    /// The ids of the nodes in each project reference cycle.
+    cycles: &'a [Vec<usize>],
+    errors: Vec<JsonError<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonNode<'a> {
+    id: usize,
+    path: String,
+    kind: &'static str,
+    sdk: bool,
+    target_frameworks: &'a [String],
+    package_references: &'a [PackageReference],
+    cycle: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct JsonEdge {
+    source: usize,
+    target: usize,
+    build_only: bool,
+    redundant: bool,
+    compatible: bool,
+    in_cycle: bool,
+}
+
+#[derive(Serialize)]
+struct JsonError<'a> {
+    node: usize,
+    path: String,
+    message: &'a str,
+}
+
+pub fn write<W: std::io::Write>(writer: &mut W, graph: &Graph, root: &Path) -> std::io::Result<()> {
+    let json_graph = JsonGraph {
+        schema_version: SCHEMA_VERSION,
+        root,
+        nodes: graph
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(id, node)| JsonNode {
+                id,
+                path: node.path_parts().join("/"),
+                kind: node.kind.as_str(),
+                sdk: node.is_sdk,
+                target_frameworks: &node.target_frameworks,
+                package_references: &node.package_references,
+                cycle: node.cycle,
+            })
+            .collect(),
+        edges: graph
+            .edges
+            .iter()
+            .map(|edge| JsonEdge {
+                source: edge.source,
+                target: edge.target,
+                build_only: edge.build_only,
+                redundant: edge.redundant,
+                compatible: edge.compatible,
+                in_cycle: edge.in_cycle,
+            })
+            .collect(),
+        cycles: &graph.cycles,
+        errors: graph
+            .nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(id, node)| {
+                node.error.as_deref().map(|message| JsonError {
+                    node: id,
+                    path: node.path_parts().join("/"),
+                    message,
+                })
+            })
+            .collect(),
+    };
+
+    serde_json::to_writer_pretty(&mut *writer, &json_graph)?;
+    writeln!(writer)
+}
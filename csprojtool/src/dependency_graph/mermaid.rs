@@ -0,0 +1,109 @@
+use super::model::*;
+
+pub fn write<W: std::io::Write>(writer: &mut W, graph: &Graph) -> std::io::Result<()> {
+    writeln!(writer, "flowchart LR")?;
+
+    for (index, node) in graph.nodes.iter().enumerate() {
+        let mut label = node.path_parts().join("/");
+        if node.kind != NodeKind::Unreadable {
+            label.push_str(&format!("<br/>[{}]", node.target_frameworks.join(", ")));
+        }
+        let label = escape(&label);
+
+        match node.kind {
+            NodeKind::Executable => writeln!(writer, "  n{}[\"{}\"]", index, label)?,
+            NodeKind::Library => writeln!(writer, "  n{}(\"{}\")", index, label)?,
+            NodeKind::Unreadable => writeln!(writer, "  n{}{{{{\"{}\"}}}}", index, label)?,
+        }
+    }
+
+    for (family_index, (family, packages)) in graph.package_families().into_iter().enumerate() {
+        if let Some(family) = family {
+            writeln!(
+                writer,
+                "  subgraph f{} [\"{}\"]",
+                family_index,
+                escape(family)
+            )?;
+        }
+        for index in packages {
+            writeln!(
+                writer,
+                "  p{}[/\"{}\"/]",
+                index,
+                escape(&graph.packages[index].name)
+            )?;
+        }
+        if family.is_some() {
+            writeln!(writer, "  end")?;
+        }
+    }
+
+    for (index, edge) in graph.edges.iter().enumerate() {
+        let arrow = if edge.redundant { "-.->" } else { "-->" };
+        writeln!(writer, "  n{} {} n{}", edge.source, arrow, edge.target)?;
+        if let Some(color) = edge.color() {
+            writeln!(writer, "  linkStyle {} stroke:{}", index, color)?;
+        }
+    }
+
+    // Link styles are addressed by the order in which the links are defined.
+    for (index, edge) in graph.package_edges.iter().enumerate() {
+        writeln!(
+            writer,
+            "  n{} -. \"{}\" .-> p{}",
+            edge.source,
+            escape(&edge.version),
+            edge.target
+        )?;
+        if let Some(color) = edge.color() {
+            writeln!(
+                writer,
+                "  linkStyle {} stroke:{}",
+                graph.edges.len() + index,
+                color
+            )?;
+        }
+    }
+
+    writeln!(writer, "  classDef sdk fill:{}", SDK_COLOR)?;
+    writeln!(writer, "  classDef legacy fill:{}", LEGACY_COLOR)?;
+    writeln!(writer, "  classDef unreadable fill:{}", ERROR_COLOR)?;
+    writeln!(
+        writer,
+        "  classDef cycle stroke:{},stroke-width:3px",
+        CYCLE_COLOR
+    )?;
+    writeln!(writer, "  classDef package fill:{}", PACKAGE_COLOR)?;
+    writeln!(
+        writer,
+        "  classDef conflict stroke:{},stroke-width:3px",
+        CONFLICT_COLOR
+    )?;
+
+    for (index, node) in graph.nodes.iter().enumerate() {
+        let class = match (node.kind, node.is_sdk) {
+            (NodeKind::Unreadable, _) => "unreadable",
+            (_, true) => "sdk",
+            (_, false) => "legacy",
+        };
+        writeln!(writer, "  class n{} {}", index, class)?;
+        if node.cycle.is_some() {
+            writeln!(writer, "  class n{} cycle", index)?;
+        }
+    }
+
+    for (index, package) in graph.packages.iter().enumerate() {
+        writeln!(writer, "  class p{} package", index)?;
+        if package.conflict {
+            writeln!(writer, "  class p{} conflict", index)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Mermaid labels are quoted strings that support entity codes.
+fn escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
@@ -0,0 +1,423 @@
+use crate::csproj::*;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+
+pub const SDK_COLOR: &str = "#7fc79f";
+pub const LEGACY_COLOR: &str = "#fdc086";
+pub const MIXED_COLOR: &str = "#ffff99";
+pub const ERROR_COLOR: &str = "#ff0000";
+pub const CYCLE_COLOR: &str = "#ff00ff";
+pub const INCOMPATIBLE_COLOR: &str = "#ff0000";
+pub const REDUNDANT_COLOR: &str = "#e2e2e2";
+pub const PACKAGE_COLOR: &str = "#beaed4";
+pub const CONFLICT_COLOR: &str = "#ff0000";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Executable,
+    Library,
+    /// The project could not be read or parsed.
+    Unreadable,
+}
+
+impl NodeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NodeKind::Executable => "exe",
+            NodeKind::Library => "library",
+            NodeKind::Unreadable => "unreadable",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub path: PathBuf,
+    pub kind: NodeKind,
+    pub is_sdk: bool,
+    pub target_frameworks: Vec<String>,
+    pub package_references: Vec<PackageReference>,
+    pub error: Option<String>,
+    /// Index into `Graph::cycles`.
+    pub cycle: Option<usize>,
+}
+
+impl Node {
+    /// The components of the path, used to spread long labels over multiple lines.
+    pub fn path_parts(&self) -> Vec<String> {
+        self.path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    pub fn fill_color(&self) -> &'static str {
+        match (self.kind, self.is_sdk) {
+            (NodeKind::Unreadable, _) => ERROR_COLOR,
+            (_, true) => SDK_COLOR,
+            (_, false) => LEGACY_COLOR,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Edge {
+    pub source: usize,
+    pub target: usize,
+    /// The reference does not reference the output assembly of the target, it only affects the build order.
+    pub build_only: bool,
+    /// The target is also reachable through a longer path.
+    pub redundant: bool,
+    /// Every target framework of the source can consume one of the target frameworks of the target.
+    pub compatible: bool,
+    /// The source and target are part of the same cycle.
+    pub in_cycle: bool,
+}
+
+impl Edge {
+    /// The color that highlights the most important property of the edge, if any.
+    pub fn color(&self) -> Option<&'static str> {
+        if !self.compatible {
+            Some(INCOMPATIBLE_COLOR)
+        } else if self.in_cycle {
+            Some(CYCLE_COLOR)
+        } else if self.redundant {
+            Some(REDUNDANT_COLOR)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PackageNode {
+    pub name: String,
+    /// The package name up to the configured number of segments, used to group related packages.
+    pub family: Option<String>,
+    /// The package is referenced with different versions within the closure of a project.
+    pub conflict: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct PackageEdge {
+    /// Index into `Graph::nodes`.
+    pub source: usize,
+    /// Index into `Graph::packages`.
+    pub target: usize,
+    pub version: String,
+    /// The version differs from another reference to the same package within the closure of a project.
+    pub conflict: bool,
+}
+
+impl PackageEdge {
+    pub fn color(&self) -> Option<&'static str> {
+        if self.conflict {
+            Some(CONFLICT_COLOR)
+        } else {
+            None
+        }
+    }
+}
+
+/// A project reference graph that the output formats are written from.
+#[derive(Debug, Clone)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+    /// The indices of the nodes in each project reference cycle.
+    pub cycles: Vec<Vec<usize>>,
+    /// Empty unless packages were added with `Graph::add_packages`.
+    pub packages: Vec<PackageNode>,
+    pub package_edges: Vec<PackageEdge>,
+}
+
+impl Graph {
+    pub fn new(projects: &[(PathBuf, Result<Project, Error>)]) -> Self {
+        let outgoing = project_edges(projects);
+        let cycles = crate::graph::cycles(&outgoing);
+
+        let mut cycle_of = vec![None; projects.len()];
+        for (cycle_index, cycle) in cycles.iter().enumerate() {
+            for &node in cycle.iter() {
+                cycle_of[node] = Some(cycle_index);
+            }
+        }
+
+        let nodes = projects
+            .iter()
+            .zip(cycle_of.iter())
+            .map(|((path, project), &cycle)| match project {
+                Ok(project) => Node {
+                    path: path.clone(),
+                    kind: if project.is_exe {
+                        NodeKind::Executable
+                    } else {
+                        NodeKind::Library
+                    },
+                    is_sdk: project.is_sdk,
+                    target_frameworks: project.target_frameworks.clone(),
+                    package_references: project.package_references.clone(),
+                    error: None,
+                    cycle,
+                },
+                Err(e) => Node {
+                    path: path.clone(),
+                    kind: NodeKind::Unreadable,
+                    is_sdk: false,
+                    target_frameworks: Vec::new(),
+                    package_references: Vec::new(),
+                    error: Some(e.to_string()),
+                    cycle,
+                },
+            })
+            .collect();
+
+        let reachability = crate::graph::Reachability::new(&outgoing);
+
+        let edges = outgoing
+            .iter()
+            .enumerate()
+            .flat_map(|(source, targets)| targets.iter().map(move |&target| (source, target)))
+            .map(|(source, target)| {
+                let in_cycle = cycle_of[source].is_some() && cycle_of[source] == cycle_of[target];
+                let build_only = match &projects[source].1 {
+                    Ok(project) => project
+                        .build_only_project_references
+                        .contains(&projects[target].0),
+                    Err(_) => false,
+                };
+                Edge {
+                    source,
+                    target,
+                    build_only,
+                    redundant: reachability.is_redundant(source, target),
+                    compatible: compatible_dependency(&projects[source].1, &projects[target].1),
+                    in_cycle,
+                }
+            })
+            .collect();
+
+        Graph {
+            nodes,
+            edges,
+            cycles,
+            packages: Vec::new(),
+            package_edges: Vec::new(),
+        }
+    }
+
+    /// Adds a node for every referenced package and marks the references whose version conflicts with another
+    /// reference in the closure of some project. Packages are grouped by the first `family_depth` segments of their
+    /// names if given.
+    pub fn add_packages(
+        &mut self,
+        projects: &[(PathBuf, Result<Project, Error>)],
+        family_depth: Option<usize>,
+    ) {
+        // Package ids are case insensitive, the first spelling is kept for display.
+        let mut names = BTreeMap::<String, &str>::new();
+        for package in projects
+            .iter()
+            .filter_map(|(_, project)| project.as_ref().ok())
+            .flat_map(|project| project.package_references.iter())
+        {
+            names
+                .entry(package.name.to_lowercase())
+                .or_insert(package.name.as_str());
+        }
+
+        self.packages = names
+            .values()
+            .map(|name| PackageNode {
+                name: name.to_string(),
+                family: family_depth.map(|depth| package_family(name, depth)),
+                conflict: false,
+            })
+            .collect();
+
+        let name_to_index = names
+            .keys()
+            .enumerate()
+            .map(|(index, name)| (name.as_str(), index))
+            .collect::<HashMap<_, _>>();
+
+        self.package_edges = projects
+            .iter()
+            .enumerate()
+            .filter_map(|(source, (_, project))| Some((source, project.as_ref().ok()?)))
+            .flat_map(|(source, project)| {
+                project
+                    .package_references
+                    .iter()
+                    .map(move |package| (source, package))
+            })
+            .map(|(source, package)| PackageEdge {
+                source,
+                target: name_to_index[package.name.to_lowercase().as_str()],
+                version: package.version.clone(),
+                conflict: false,
+            })
+            .collect();
+
+        let mut outgoing = vec![Vec::new(); self.nodes.len()];
+        for edge in self.edges.iter() {
+            outgoing[edge.source].push(edge.target);
+        }
+
+        for root in 0..self.nodes.len() {
+            let closure = crate::graph::reachable(&outgoing, root);
+
+            let mut versions = BTreeMap::<usize, BTreeSet<&str>>::new();
+            for edge in self
+                .package_edges
+                .iter()
+                .filter(|edge| closure[edge.source])
+            {
+                versions
+                    .entry(edge.target)
+                    .or_default()
+                    .insert(edge.version.as_str());
+            }
+
+            let conflicts = versions
+                .into_iter()
+                .filter(|(_, versions)| versions.len() > 1)
+                .map(|(package, _)| package)
+                .collect::<BTreeSet<_>>();
+
+            for edge in self.package_edges.iter_mut() {
+                if closure[edge.source] && conflicts.contains(&edge.target) {
+                    edge.conflict = true;
+                }
+            }
+            for &package in conflicts.iter() {
+                self.packages[package].conflict = true;
+            }
+        }
+    }
+
+    /// The packages grouped by family, packages without a family come first.
+    pub fn package_families(&self) -> BTreeMap<Option<&str>, Vec<usize>> {
+        let mut families = BTreeMap::<Option<&str>, Vec<usize>>::new();
+        for (index, package) in self.packages.iter().enumerate() {
+            families
+                .entry(package.family.as_deref())
+                .or_default()
+                .push(index);
+        }
+        families
+    }
+}
+
+fn package_family(name: &str, depth: usize) -> String {
+    name.split('.')
+        .take(depth.max(1))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// The outgoing edges of every project, references to projects that are not part of `projects` are left out.
+pub fn project_edges(projects: &[(PathBuf, Result<Project, Error>)]) -> Vec<Vec<usize>> {
+    let nodes = projects
+        .iter()
+        .enumerate()
+        .map(|(index, (path, _))| (path.clone(), index))
+        .collect::<HashMap<PathBuf, usize>>();
+
+    projects
+        .iter()
+        .map(|(_, project)| match project {
+            Ok(project) => project
+                .project_references
+                .iter()
+                .filter_map(|path| nodes.get(path).copied())
+                .collect(),
+            Err(_) => Vec::new(),
+        })
+        .collect()
+}
+
+fn compatible_dependency(source: &Result<Project, Error>, target: &Result<Project, Error>) -> bool {
+    match (source, target) {
+        (Ok(source), Ok(target)) => crate::tfm::incompatible_frameworks(
+            &source.target_frameworks,
+            &target.target_frameworks,
+        )
+        .is_empty(),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(
+        path: &str,
+        project_references: &[&str],
+        packages: &[(&str, &str)],
+    ) -> (PathBuf, Result<Project, Error>) {
+        (
+            PathBuf::from(path),
+            Ok(Project {
+                path: PathBuf::from(path),
+                is_sdk: true,
+                is_exe: false,
+                project_guid: None,
+                target_frameworks: vec!["net6.0".to_string()],
+                project_references: project_references.iter().map(PathBuf::from).collect(),
+                build_only_project_references: Vec::new(),
+                private_project_references: Vec::new(),
+                disable_transitive_project_references: false,
+                package_references: packages
+                    .iter()
+                    .map(|&(name, version)| PackageReference {
+                        name: name.to_string(),
+                        version: version.to_string(),
+                    })
+                    .collect(),
+                imports: Vec::new(),
+            }),
+        )
+    }
+
+    #[test]
+    fn package_conflicts_are_limited_to_closures() {
+        let projects = vec![
+            project("a", &["b"], &[("Newtonsoft.Json", "13.0.1")]),
+            project("b", &[], &[("newtonsoft.json", "12.0.3")]),
+            project(
+                "c",
+                &[],
+                &[("Newtonsoft.Json", "11.0.1"), ("Serilog", "2.10.0")],
+            ),
+        ];
+
+        let mut graph = Graph::new(&projects);
+        graph.add_packages(&projects, Some(1));
+
+        assert_eq!(
+            graph
+                .packages
+                .iter()
+                .map(|package| (
+                    package.name.as_str(),
+                    package.family.as_deref(),
+                    package.conflict
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Newtonsoft.Json", Some("Newtonsoft"), true),
+                ("Serilog", Some("Serilog"), false),
+            ]
+        );
+        assert_eq!(
+            graph
+                .package_edges
+                .iter()
+                .map(|edge| (edge.source, edge.conflict))
+                .collect::<Vec<_>>(),
+            vec![(0, true), (1, true), (2, false), (2, false)]
+        );
+    }
+}
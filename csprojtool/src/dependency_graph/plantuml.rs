@@ -0,0 +1,74 @@
+use super::model::*;
+
+pub fn write<W: std::io::Write>(writer: &mut W, graph: &Graph) -> std::io::Result<()> {
+    writeln!(writer, "@startuml")?;
+    writeln!(writer, "left to right direction")?;
+
+    for (index, node) in graph.nodes.iter().enumerate() {
+        let mut label = node.path_parts().join("/");
+        if node.kind != NodeKind::Unreadable {
+            label.push_str(&format!("\\n[{}]", node.target_frameworks.join(", ")));
+        }
+
+        let mut color = node.fill_color().to_string();
+        if node.cycle.is_some() {
+            color.push_str(&format!(";line:{};line.bold", &CYCLE_COLOR[1..]));
+        }
+
+        writeln!(
+            writer,
+            "component \"{}\" as n{} <<{}>> {}",
+            label.replace('"', "'"),
+            index,
+            node.kind.as_str(),
+            color
+        )?;
+    }
+
+    for (family, packages) in graph.package_families() {
+        if let Some(family) = family {
+            writeln!(writer, "package \"{}\" {{", family)?;
+        }
+        for index in packages {
+            let package = &graph.packages[index];
+            let mut color = PACKAGE_COLOR.to_string();
+            if package.conflict {
+                color.push_str(&format!(";line:{};line.bold", &CONFLICT_COLOR[1..]));
+            }
+            writeln!(
+                writer,
+                "component \"{}\" as p{} <<package>> {}",
+                package.name, index, color
+            )?;
+        }
+        if family.is_some() {
+            writeln!(writer, "}}")?;
+        }
+    }
+
+    for edge in graph.edges.iter() {
+        let style = match (edge.color(), edge.redundant) {
+            (Some(color), true) => format!("[{},dashed]", color),
+            (Some(color), false) => format!("[{}]", color),
+            (None, true) => "[dashed]".to_string(),
+            (None, false) => String::new(),
+        };
+        writeln!(writer, "n{} -{}-> n{}", edge.source, style, edge.target)?;
+    }
+
+    for edge in graph.package_edges.iter() {
+        let style = match edge.color() {
+            Some(color) => format!("[{},dashed]", color),
+            None => "[dashed]".to_string(),
+        };
+        writeln!(
+            writer,
+            "n{} -{}-> p{} : {}",
+            edge.source, style, edge.target, edge.version
+        )?;
+    }
+
+    writeln!(writer, "@enduml")?;
+
+    Ok(())
+}
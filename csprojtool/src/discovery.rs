@@ -0,0 +1,369 @@
+use crossbeam_channel::Sender;
+use ignore::ParallelVisitor;
+use ignore::ParallelVisitorBuilder;
+use log::debug;
+use log::warn;
+
+use crate::csproj::*;
+use crate::path_extensions::*;
+use crate::utils::entry_is_csproj;
+use crate::utils::find_git_root;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options<'a> {
+    /// A project file, or a directory whose projects are the seeds.
+    pub search_path: &'a Path,
+    /// Only projects matching the glob, relative to the search directory, are seeds. Ignored when the search path
+    /// is a file.
+    pub glob: Option<&'a globset::GlobMatcher>,
+    pub follow_incoming_project_references: bool,
+    pub follow_outgoing_project_references: bool,
+}
+
+/// The projects under the root directory together with the projects they reference from outside of it.
+#[derive(Debug)]
+pub struct Discovery {
+    pub root_dir: PathBuf,
+    /// The search path itself, or its directory when it is a file.
+    pub search_dir: PathBuf,
+    /// Absolute project paths with the result of reading and parsing them, sorted by path.
+    pub projects: Vec<(PathBuf, Result<Project, Error>)>,
+    /// The projects selected by the search path and glob.
+    pub seeds: Vec<bool>,
+    /// The seeds and the projects reached from them by following project references.
+    pub included: Vec<bool>,
+}
+
+impl Discovery {
+    /// The included projects that could be read and parsed, the others are logged and left out.
+    pub fn into_included_projects(self) -> Vec<Project> {
+        self.projects
+            .into_iter()
+            .zip(self.included)
+            .filter(|(_, included)| *included)
+            .filter_map(|((path, project), _)| match project {
+                Ok(project) => Some(project),
+                Err(e) => {
+                    warn!(
+                        "Ignoring project at {} due parsing failure: {}",
+                        path.display(),
+                        e
+                    );
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// Walks the root directory of the search path, respecting ignore files, reads all projects in parallel and then
+/// loads the projects they reference from outside of the walked directories. Never changes the current directory.
+pub fn discover(options: Options) -> Result<Discovery, Error> {
+    let Options {
+        search_path,
+        glob,
+        follow_incoming_project_references,
+        follow_outgoing_project_references,
+    } = options;
+
+    let Root {
+        root_dir,
+        search_path,
+    } = root_dir(search_path)?;
+    let search_is_file = std::fs::metadata(&search_path)?.is_file();
+    let search_dir = if search_is_file {
+        search_path.parent().unwrap_or(&search_path).to_path_buf()
+    } else {
+        search_path.clone()
+    };
+
+    let mut projects = find_and_parse_projects(&root_dir)
+        .into_iter()
+        .collect::<BTreeMap<_, _>>();
+
+    if search_is_file && !projects.contains_key(&search_path) {
+        let project = read_and_parse_project(search_path.clone());
+        projects.insert(search_path.clone(), project);
+    }
+
+    // Load referenced projects that are ignored or outside of the root directory.
+    loop {
+        let missing = projects
+            .values()
+            .filter_map(|project| project.as_ref().ok())
+            .flat_map(|project| project.project_references.iter())
+            .filter(|path| !projects.contains_key(*path))
+            .cloned()
+            .collect::<BTreeSet<_>>();
+
+        if missing.is_empty() {
+            break;
+        }
+
+        for path in missing {
+            debug!("Loading referenced project {}", path.display());
+            let project = read_and_parse_project(path.clone());
+            projects.insert(path, project);
+        }
+    }
+
+    let projects = projects.into_iter().collect::<Vec<_>>();
+
+    let seeds = projects
+        .iter()
+        .map(|(path, _)| {
+            if search_is_file {
+                *path == search_path
+            } else {
+                path.starts_with(&search_dir)
+                    && match glob {
+                        Some(glob) => glob.is_match(relative_path(&search_dir, path)),
+                        None => true,
+                    }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let path_to_index = projects
+        .iter()
+        .enumerate()
+        .map(|(index, (path, _))| (path.as_path(), index))
+        .collect::<BTreeMap<_, _>>();
+
+    let edges = projects
+        .iter()
+        .enumerate()
+        .flat_map(|(from, (_, project))| {
+            project
+                .iter()
+                .flat_map(|project| project.project_references.iter())
+                .filter_map(|path| path_to_index.get(path.as_path()).copied())
+                .map(move |to| (from, to))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut included = seeds.clone();
+    follow_project_references(
+        &edges,
+        &mut included,
+        follow_incoming_project_references,
+        follow_outgoing_project_references,
+    );
+
+    Ok(Discovery {
+        root_dir,
+        search_dir,
+        projects,
+        seeds,
+        included,
+    })
+}
+
+/// Parses all projects under the root directory in parallel, leaving out the projects that fail to parse.
+pub fn find_projects(root_dir: &Path) -> Vec<Project> {
+    find_and_parse_projects(root_dir)
+        .into_iter()
+        .filter_map(|(path, project)| match project {
+            Ok(project) => Some(project),
+            Err(e) => {
+                warn!(
+                    "Ignoring project at {} due parsing failure: {}",
+                    path.display(),
+                    e
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+/// Reads and parses all projects under the root directory in parallel, respecting ignore files.
+pub fn find_and_parse_projects(root_dir: &Path) -> Vec<(PathBuf, Result<Project, Error>)> {
+    let (sender, receiver) = crossbeam_channel::unbounded();
+
+    let mut visitor_builder = CollectorBuilder { sender };
+
+    let walk_builder = ignore::WalkBuilder::new(root_dir);
+    walk_builder.build_parallel().visit(&mut visitor_builder);
+
+    drop(visitor_builder);
+
+    receiver.into_iter().flatten().collect::<Vec<_>>()
+}
+
+/// Finds the paths of all projects under the root directory, respecting ignore files.
+pub fn find_project_files(root_dir: &Path) -> Vec<PathBuf> {
+    let mut paths = ignore::WalkBuilder::new(root_dir)
+        .build()
+        .filter_map(|entry| match entry {
+            Ok(entry) if entry_is_csproj(&entry) => Some(entry.into_path()),
+            Ok(_) => None,
+            Err(e) => {
+                warn!("Skipping entry while searching for projects: {}", e);
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+/// Computes the project reference edges as (from, to) index pairs into `projects`.
+pub fn project_edges(projects: &[Project], root_dir: &Path) -> Vec<(usize, usize)> {
+    let path_to_project_index = projects
+        .iter()
+        .enumerate()
+        .map(|(index, project)| (project.path.to_owned(), index))
+        .collect::<BTreeMap<_, _>>();
+
+    projects
+        .iter()
+        .enumerate()
+        .flat_map(|(from_index, project)| {
+            project
+                .project_references
+                .iter()
+                .filter_map(|to_path| {
+                    if let Some(to_index) = path_to_project_index.get(to_path).copied() {
+                        Some((from_index, to_index))
+                    } else {
+                        warn!(
+                            "Reference from {} to {} not found in parsed projects under {}!",
+                            project.path.display(),
+                            to_path.display(),
+                            root_dir.display()
+                        );
+                        None
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>()
+}
+
+/// Grows the set of included projects by following the edges until a fixed point is reached.
+pub fn follow_project_references(
+    edges: &[(usize, usize)],
+    included: &mut [bool],
+    follow_incoming_project_references: bool,
+    follow_outgoing_project_references: bool,
+) {
+    // Follow incoming references
+    if follow_incoming_project_references {
+        while let Some((from, _)) = edges
+            .iter()
+            .copied()
+            .find(|&(from, to)| !included[from] && included[to])
+        {
+            included[from] = true;
+        }
+    }
+
+    // Follow outgoing references
+    if follow_outgoing_project_references {
+        while let Some((_, to)) = edges
+            .iter()
+            .copied()
+            .find(|&(from, to)| included[from] && !included[to])
+        {
+            included[to] = true;
+        }
+    }
+}
+
+/// The root directory that is searched for projects.
+#[derive(Debug)]
+pub struct Root {
+    /// The git root containing the search path, or the current directory.
+    pub root_dir: PathBuf,
+    /// The canonical search path, comparable with the project paths.
+    pub search_path: PathBuf,
+}
+
+/// Finds the git root containing the search path, falling back to the current directory. Both paths are
+/// canonicalized like the project paths.
+pub fn root_dir(search_path: &Path) -> std::io::Result<Root> {
+    let search_path = std::fs::canonicalize(search_path)?;
+    let search_meta = std::fs::metadata(&search_path)?;
+
+    let search_dir = if search_meta.is_file() {
+        search_path.parent().unwrap_or(&search_path)
+    } else {
+        &search_path
+    };
+
+    let root_dir = match find_git_root(search_dir) {
+        Some(root_dir) => {
+            debug!("Using {} as root directory.", root_dir.display());
+            root_dir.to_owned()
+        }
+        None => {
+            let current_dir = std::fs::canonicalize(std::env::current_dir()?)?;
+            warn!(
+                "No git root found, using the current directory {} as root directory.",
+                current_dir.display()
+            );
+            current_dir
+        }
+    };
+
+    Ok(Root {
+        root_dir,
+        search_path,
+    })
+}
+
+struct Collector {
+    projects: Vec<(PathBuf, Result<Project, Error>)>,
+    sender: Sender<Vec<(PathBuf, Result<Project, Error>)>>,
+}
+
+impl ParallelVisitor for Collector {
+    fn visit(&mut self, entry: Result<ignore::DirEntry, ignore::Error>) -> ignore::WalkState {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("Skipping entry while searching for projects: {}", e);
+                return ignore::WalkState::Continue;
+            }
+        };
+
+        if entry_is_csproj(&entry) {
+            match std::fs::canonicalize(entry.path()) {
+                Ok(path) => {
+                    let project = read_and_parse_project(path.clone());
+                    self.projects.push((path, project));
+                }
+                Err(e) => warn!("Skipping project at {}: {}", entry.path().display(), e),
+            }
+        }
+
+        ignore::WalkState::Continue
+    }
+}
+
+impl Drop for Collector {
+    fn drop(&mut self) {
+        let projects = std::mem::take(&mut self.projects);
+        // The receiver outlives the walk, sending can only fail if it panicked.
+        let _ = self.sender.send(projects);
+    }
+}
+
+struct CollectorBuilder {
+    sender: Sender<Vec<(PathBuf, Result<Project, Error>)>>,
+}
+
+impl<'s> ParallelVisitorBuilder<'s> for CollectorBuilder {
+    fn build(&mut self) -> Box<dyn ParallelVisitor + 's> {
+        Box::new(Collector {
+            projects: Vec::new(),
+            sender: self.sender.clone(),
+        })
+    }
+}
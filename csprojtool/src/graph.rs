@@ -0,0 +1,449 @@
+//! Algorithms over project reference graphs represented as adjacency lists of node indices.
+
+/// Converts (from, to) edges into adjacency lists of outgoing edges.
+pub fn outgoing_edges(n: usize, edges: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let mut outgoing = vec![Vec::new(); n];
+    for &(from, to) in edges {
+        outgoing[from].push(to);
+    }
+    outgoing
+}
+
+/// Computes the strongly connected components using an iterative version of Tarjan's algorithm.
+/// Components are returned in reverse topological order: dependencies come before their dependents.
+pub fn strongly_connected_components(outgoing: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    const UNVISITED: usize = usize::MAX;
+
+    let n = outgoing.len();
+    let mut index = vec![UNVISITED; n];
+    let mut low_link = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = Vec::new();
+    let mut components = Vec::new();
+    let mut next_index = 0;
+
+    for root in 0..n {
+        if index[root] != UNVISITED {
+            continue;
+        }
+
+        // Each frame holds a node and the position of the next edge to visit.
+        let mut frames = vec![(root, 0)];
+        index[root] = next_index;
+        low_link[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some(&(node, edge)) = frames.last() {
+            if let Some(&target) = outgoing[node].get(edge) {
+                frames.last_mut().unwrap().1 += 1;
+                if index[target] == UNVISITED {
+                    index[target] = next_index;
+                    low_link[target] = next_index;
+                    next_index += 1;
+                    stack.push(target);
+                    on_stack[target] = true;
+                    frames.push((target, 0));
+                } else if on_stack[target] {
+                    low_link[node] = low_link[node].min(index[target]);
+                }
+                continue;
+            }
+
+            frames.pop();
+            if let Some(&(parent, _)) = frames.last() {
+                low_link[parent] = low_link[parent].min(low_link[node]);
+            }
+
+            if low_link[node] == index[node] {
+                let mut component = Vec::new();
+                loop {
+                    let member = stack.pop().unwrap();
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                component.sort_unstable();
+                components.push(component);
+            }
+        }
+    }
+
+    components
+}
+
+/// Returns the strongly connected components that contain a cycle, including projects that reference themselves.
+pub fn cycles(outgoing: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    strongly_connected_components(outgoing)
+        .into_iter()
+        .filter(|component| match component.as_slice() {
+            [node] => outgoing[*node].contains(node),
+            _ => true,
+        })
+        .collect()
+}
+
+/// Assigns every node the length of the longest dependency chain below it, so nodes with equal layers can be
+/// processed in parallel once all lower layers are done. Nodes in the same cycle share a layer.
+pub fn layers(outgoing: &[Vec<usize>]) -> Vec<usize> {
+    let components = strongly_connected_components(outgoing);
+
+    let mut component_of = vec![0; outgoing.len()];
+    for (component_index, component) in components.iter().enumerate() {
+        for &node in component.iter() {
+            component_of[node] = component_index;
+        }
+    }
+
+    // Components come in reverse topological order so the layers of the dependencies are known.
+    let mut layers = vec![0; outgoing.len()];
+    for (component_index, component) in components.iter().enumerate() {
+        let layer = component
+            .iter()
+            .flat_map(|&node| outgoing[node].iter())
+            .filter(|&&target| component_of[target] != component_index)
+            .map(|&target| layers[target] + 1)
+            .max()
+            .unwrap_or_default();
+        for &node in component.iter() {
+            layers[node] = layer;
+        }
+    }
+    layers
+}
+
+/// Marks the nodes reachable from `start`, including `start` itself.
+pub fn reachable(outgoing: &[Vec<usize>], start: usize) -> Vec<bool> {
+    let mut visited = vec![false; outgoing.len()];
+    let mut stack = vec![start];
+    visited[start] = true;
+    while let Some(node) = stack.pop() {
+        for &target in outgoing[node].iter() {
+            if !visited[target] {
+                visited[target] = true;
+                stack.push(target);
+            }
+        }
+    }
+    visited
+}
+
+/// Enumerates the simple paths from `from` to any of the `targets`, shortest first, stopping after `limit` paths.
+/// Iterative deepening keeps the memory linear in the path length, a breadth first search over partial paths grows
+/// exponentially in graphs with many diamonds.
+pub fn paths(
+    outgoing: &[Vec<usize>],
+    from: usize,
+    targets: &[usize],
+    limit: usize,
+) -> Vec<Vec<usize>> {
+    // The number of edges from each node to the nearest target, nodes that can not reach a target are not visited.
+    let mut incoming = vec![Vec::new(); outgoing.len()];
+    for (source, sources_targets) in outgoing.iter().enumerate() {
+        for &target in sources_targets.iter() {
+            incoming[target].push(source);
+        }
+    }
+    let mut distance = vec![None; outgoing.len()];
+    let mut queue = std::collections::VecDeque::new();
+    for &target in targets.iter() {
+        if distance[target].is_none() {
+            distance[target] = Some(0);
+            queue.push_back(target);
+        }
+    }
+    while let Some(node) = queue.pop_front() {
+        let next_distance = distance[node].map(|distance| distance + 1);
+        for &source in incoming[node].iter() {
+            if distance[source].is_none() {
+                distance[source] = next_distance;
+                queue.push_back(source);
+            }
+        }
+    }
+
+    let shortest = match distance[from] {
+        Some(shortest) => shortest,
+        None => return Vec::new(),
+    };
+    let longest = distance
+        .iter()
+        .filter(|distance| distance.is_some())
+        .count()
+        - 1;
+
+    let mut search = PathSearch {
+        outgoing,
+        targets,
+        distance: &distance,
+        path: vec![from],
+        on_path: vec![false; outgoing.len()],
+        limit,
+        paths: Vec::new(),
+    };
+    search.on_path[from] = true;
+    for length in shortest..=longest {
+        if search.paths.len() >= limit {
+            break;
+        }
+        search.extend(length);
+    }
+    search.paths
+}
+
+struct PathSearch<'a> {
+    outgoing: &'a [Vec<usize>],
+    targets: &'a [usize],
+    distance: &'a [Option<usize>],
+    path: Vec<usize>,
+    on_path: Vec<bool>,
+    limit: usize,
+    paths: Vec<Vec<usize>>,
+}
+
+impl PathSearch<'_> {
+    /// Depth first search for the extensions of the path with exactly `remaining` more edges that end in a target.
+    fn extend(&mut self, remaining: usize) {
+        let last = *self.path.last().unwrap();
+        if remaining == 0 {
+            if self.targets.contains(&last) {
+                self.paths.push(self.path.clone());
+            }
+            return;
+        }
+
+        for &next in self.outgoing[last].iter() {
+            if self.paths.len() >= self.limit {
+                return;
+            }
+            if self.on_path[next]
+                || !matches!(self.distance[next], Some(distance) if distance < remaining)
+            {
+                continue;
+            }
+            self.path.push(next);
+            self.on_path[next] = true;
+            self.extend(remaining - 1);
+            self.on_path[next] = false;
+            self.path.pop();
+        }
+    }
+}
+
+/// A fixed size set of indices packed into words.
+#[derive(Debug, Clone)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    pub fn insert(&mut self, index: usize) {
+        self.words[index / 64] |= 1 << (index % 64);
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        self.words[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    pub fn union_with(&mut self, other: &BitSet) {
+        for (word, other_word) in self.words.iter_mut().zip(other.words.iter()) {
+            *word |= other_word;
+        }
+    }
+}
+
+/// Answers reachability queries through the condensation of the graph, where every strongly connected component is a
+/// single node. This takes a single pass in topological order and a bitset per component, which scales to thousands
+/// of projects where an N*N path matrix does not.
+#[derive(Debug, Clone)]
+pub struct Reachability {
+    component_of: Vec<usize>,
+    /// The distinct components referenced by each component, excluding itself.
+    successors: Vec<Vec<usize>>,
+    /// The components reachable from each component, excluding itself.
+    reachable: Vec<BitSet>,
+}
+
+impl Reachability {
+    pub fn new(outgoing: &[Vec<usize>]) -> Self {
+        let components = strongly_connected_components(outgoing);
+
+        let mut component_of = vec![0; outgoing.len()];
+        for (component_index, component) in components.iter().enumerate() {
+            for &node in component.iter() {
+                component_of[node] = component_index;
+            }
+        }
+
+        let successors = components
+            .iter()
+            .enumerate()
+            .map(|(component_index, component)| {
+                let mut successors = component
+                    .iter()
+                    .flat_map(|&node| outgoing[node].iter())
+                    .map(|&target| component_of[target])
+                    .filter(|&target| target != component_index)
+                    .collect::<Vec<_>>();
+                successors.sort_unstable();
+                successors.dedup();
+                successors
+            })
+            .collect::<Vec<_>>();
+
+        // Components come in reverse topological order so the successors are complete before they are used.
+        let mut reachable = Vec::<BitSet>::with_capacity(components.len());
+        for component_successors in successors.iter() {
+            let mut set = BitSet::new(components.len());
+            for &successor in component_successors.iter() {
+                set.insert(successor);
+                set.union_with(&reachable[successor]);
+            }
+            reachable.push(set);
+        }
+
+        Self {
+            component_of,
+            successors,
+            reachable,
+        }
+    }
+
+    /// Whether there is a path from `from` to `to`, every node reaches itself.
+    pub fn reaches(&self, from: usize, to: usize) -> bool {
+        let (from, to) = (self.component_of[from], self.component_of[to]);
+        from == to || self.reachable[from].contains(to)
+    }
+
+    /// Whether the edge from `from` to `to` is implied by a longer path. Edges within a cycle are never redundant.
+    pub fn is_redundant(&self, from: usize, to: usize) -> bool {
+        let (from, to) = (self.component_of[from], self.component_of[to]);
+        from != to
+            && self.successors[from]
+                .iter()
+                .any(|&via| via != to && self.reachable[via].contains(to))
+    }
+}
+
+/// Finds the edges that are implied by a longer path as `(from, to, via)` triples, where `via` is the direct
+/// dependency of `from` that also reaches `to`. The longer path may only use the `transitive` edges, a subset of
+/// `outgoing` whose targets flow to the consumers of their source. Sources that are part of a cycle are skipped
+/// because the transitive reduction of a cycle is not unique.
+pub fn redundant_edges(
+    outgoing: &[Vec<usize>],
+    transitive: &[Vec<usize>],
+) -> Vec<(usize, usize, usize)> {
+    let reachability = Reachability::new(transitive);
+
+    let mut in_cycle = vec![false; outgoing.len()];
+    for cycle in cycles(outgoing) {
+        for node in cycle {
+            in_cycle[node] = true;
+        }
+    }
+
+    let mut redundant = Vec::new();
+
+    for (from, targets) in outgoing.iter().enumerate() {
+        if in_cycle[from] {
+            continue;
+        }
+
+        for &to in targets.iter() {
+            let via = transitive[from]
+                .iter()
+                .copied()
+                .find(|&via| via != to && reachability.reaches(via, to));
+            if let Some(via) = via {
+                redundant.push((from, to, via));
+            }
+        }
+    }
+
+    redundant.sort_unstable();
+    redundant.dedup_by_key(|&mut (from, to, _)| (from, to));
+    redundant
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strongly_connected_components_are_in_reverse_topological_order() {
+        // 0 -> 1 -> 2 -> 1, 2 -> 3
+        let outgoing = vec![vec![1], vec![2], vec![1, 3], vec![]];
+        assert_eq!(
+            strongly_connected_components(&outgoing),
+            vec![vec![3], vec![1, 2], vec![0]]
+        );
+    }
+
+    #[test]
+    fn cycles_include_self_references() {
+        let outgoing = vec![vec![0], vec![2], vec![1], vec![0]];
+        assert_eq!(cycles(&outgoing), vec![vec![0], vec![1, 2]]);
+    }
+
+    #[test]
+    fn redundant_edges_are_implied_by_longer_paths() {
+        // 0 -> 1 -> 2 -> 3, 0 -> 2, 0 -> 3, 4 -> 5 -> 4, 4 -> 2
+        let outgoing = vec![vec![1, 2, 3], vec![2], vec![3], vec![], vec![5, 2], vec![4]];
+        assert_eq!(
+            redundant_edges(&outgoing, &outgoing),
+            vec![(0, 2, 1), (0, 3, 1)]
+        );
+
+        // 2 -> 3 does not flow to the consumers of 2.
+        let transitive = vec![vec![1, 2, 3], vec![2], vec![], vec![], vec![5, 2], vec![4]];
+        assert_eq!(redundant_edges(&outgoing, &transitive), vec![(0, 2, 1)]);
+    }
+
+    #[test]
+    fn layers_are_longest_dependency_chains() {
+        // 0 -> 1 -> 2, 0 -> 2, 3 -> 2, 4 -> 5 -> 4 -> 3
+        let outgoing = vec![vec![1, 2], vec![2], vec![], vec![2], vec![5, 3], vec![4]];
+        assert_eq!(layers(&outgoing), vec![2, 1, 0, 1, 2, 2]);
+    }
+
+    #[test]
+    fn paths_are_shortest_first() {
+        // 0 -> 1 -> 3, 0 -> 2 -> 1, 0 -> 3, 3 -> 0, 4
+        let outgoing = vec![vec![1, 2, 3], vec![3], vec![1], vec![0], vec![]];
+        assert_eq!(
+            paths(&outgoing, 0, &[3], usize::MAX),
+            vec![vec![0, 3], vec![0, 1, 3], vec![0, 2, 1, 3]]
+        );
+        assert_eq!(
+            paths(&outgoing, 0, &[3], 2),
+            vec![vec![0, 3], vec![0, 1, 3]]
+        );
+        assert_eq!(
+            paths(&outgoing, 2, &[0], usize::MAX),
+            vec![vec![2, 1, 3, 0]]
+        );
+        assert!(paths(&outgoing, 0, &[4], usize::MAX).is_empty());
+    }
+
+    #[test]
+    fn reachability_handles_cycles() {
+        // 0 -> 1 -> 2 -> 1, 2 -> 3, 0 -> 3, 1 -> 4, 2 -> 4
+        let outgoing = vec![vec![1, 3], vec![2, 4], vec![1, 3, 4], vec![], vec![]];
+        let reachability = Reachability::new(&outgoing);
+        assert!(reachability.reaches(0, 4));
+        assert!(reachability.reaches(2, 1));
+        assert!(!reachability.reaches(3, 0));
+        assert!(reachability.is_redundant(0, 3));
+        assert!(!reachability.is_redundant(1, 2));
+        assert!(!reachability.is_redundant(1, 4));
+    }
+}
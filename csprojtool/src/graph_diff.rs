@@ -0,0 +1,361 @@
+use log::warn;
+use serde::Serialize;
+
+use crate::csproj::*;
+use crate::dependency_graph::model::{LEGACY_COLOR, SDK_COLOR};
+use crate::discovery::{root_dir, Root};
+use crate::path_extensions::*;
+use crate::utils::{git, path_extension_is_csproj, OutputFormat};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::path::PathBuf;
+
+const ADDED_COLOR: &str = "#1a9850";
+const REMOVED_COLOR: &str = "#d73027";
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options<'a> {
+    pub search_path: &'a Path,
+    pub rev_a: &'a str,
+    pub rev_b: &'a str,
+    /// Write a dot graph with the added and removed references highlighted to this path.
+    pub dot: Option<&'a str>,
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub added_projects: Vec<PathBuf>,
+    pub removed_projects: Vec<PathBuf>,
+    pub added_references: Vec<Reference>,
+    pub removed_references: Vec<Reference>,
+    pub package_changes: Vec<PackageChange>,
+    pub framework_changes: Vec<FrameworkChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Reference {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// A package reference that was added, removed or changed version in a project that exists in both revisions.
+#[derive(Debug, Serialize)]
+pub struct PackageChange {
+    pub project: PathBuf,
+    pub package: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FrameworkChange {
+    pub project: PathBuf,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+impl Report {
+    pub fn is_empty(&self) -> bool {
+        self.added_projects.is_empty()
+            && self.removed_projects.is_empty()
+            && self.added_references.is_empty()
+            && self.removed_references.is_empty()
+            && self.package_changes.is_empty()
+            && self.framework_changes.is_empty()
+    }
+}
+
+pub fn run(options: Options) {
+    let Options {
+        search_path,
+        rev_a,
+        rev_b,
+        dot,
+        format,
+    } = options;
+
+    let current_dir = std::env::current_dir().unwrap();
+    let Root {
+        root_dir,
+        search_path,
+    } = root_dir(search_path).unwrap();
+
+    let in_scope = |project: &Project| project.path.starts_with(&search_path);
+    let before = read_projects_at(&root_dir, rev_a)
+        .into_iter()
+        .filter(|(_, project)| in_scope(project))
+        .collect::<BTreeMap<_, _>>();
+    let after = read_projects_at(&root_dir, rev_b)
+        .into_iter()
+        .filter(|(_, project)| in_scope(project))
+        .collect::<BTreeMap<_, _>>();
+
+    let report = graph_diff(&before, &after, &current_dir);
+
+    if let Some(path) = dot {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path).unwrap());
+        write_dot(&mut file, &before, &after, &current_dir).unwrap();
+    }
+
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(std::io::stdout(), &report).unwrap();
+            println!();
+        }
+        OutputFormat::Table => print_table(&report),
+    }
+}
+
+/// Reads and parses all the project files in the revision from the git object database, ignoring the working tree.
+/// Projects that fail to parse are skipped with a warning.
+pub fn read_projects_at(root_dir: &Path, rev: &str) -> BTreeMap<PathBuf, Project> {
+    git(
+        root_dir,
+        &["ls-tree", "-r", "--name-only", "--full-tree", rev],
+    )
+    .lines()
+    .filter(|path| path_extension_is_csproj(Path::new(path)))
+    .filter_map(|path| {
+        let contents = git(root_dir, &["show", &format!("{}:{}", rev, path)]);
+        let project_path = root_dir.join(path).simplify();
+        match parse_project(project_path.clone(), &contents) {
+            Ok(project) => Some((project_path, project)),
+            Err(e) => {
+                warn!("Skipping {} at {}: {}", path, rev, e);
+                None
+            }
+        }
+    })
+    .collect()
+}
+
+/// Compares two sets of projects keyed by path, paths in the report are relative to `current_dir`.
+pub fn graph_diff(
+    before: &BTreeMap<PathBuf, Project>,
+    after: &BTreeMap<PathBuf, Project>,
+    current_dir: &Path,
+) -> Report {
+    let rel = |path: &Path| relative_path(current_dir, path);
+
+    let added_projects = after
+        .keys()
+        .filter(|path| !before.contains_key(*path))
+        .map(|path| rel(path))
+        .collect();
+    let removed_projects = before
+        .keys()
+        .filter(|path| !after.contains_key(*path))
+        .map(|path| rel(path))
+        .collect();
+
+    let references_before = references(before);
+    let references_after = references(after);
+    let to_reference = |(from, to): &(&Path, &Path)| Reference {
+        from: rel(from),
+        to: rel(to),
+    };
+    let added_references = references_after
+        .difference(&references_before)
+        .map(to_reference)
+        .collect();
+    let removed_references = references_before
+        .difference(&references_after)
+        .map(to_reference)
+        .collect();
+
+    let mut package_changes = Vec::new();
+    let mut framework_changes = Vec::new();
+    for (path, project_before) in before.iter() {
+        let project_after = match after.get(path) {
+            Some(project) => project,
+            None => continue,
+        };
+
+        let packages_before = package_versions(project_before);
+        let packages_after = package_versions(project_after);
+        let names = packages_before
+            .keys()
+            .chain(packages_after.keys())
+            .collect::<BTreeSet<_>>();
+        for name in names {
+            let version_before = packages_before.get(name);
+            let version_after = packages_after.get(name);
+            if version_before != version_after {
+                package_changes.push(PackageChange {
+                    project: rel(path),
+                    package: name.to_string(),
+                    before: version_before.map(|version| version.to_string()),
+                    after: version_after.map(|version| version.to_string()),
+                });
+            }
+        }
+
+        if project_before.target_frameworks != project_after.target_frameworks {
+            framework_changes.push(FrameworkChange {
+                project: rel(path),
+                before: project_before.target_frameworks.clone(),
+                after: project_after.target_frameworks.clone(),
+            });
+        }
+    }
+
+    Report {
+        added_projects,
+        removed_projects,
+        added_references,
+        removed_references,
+        package_changes,
+        framework_changes,
+    }
+}
+
+fn references(projects: &BTreeMap<PathBuf, Project>) -> BTreeSet<(&Path, &Path)> {
+    projects
+        .iter()
+        .flat_map(|(path, project)| {
+            project
+                .project_references
+                .iter()
+                .map(move |reference| (path.as_path(), reference.as_path()))
+        })
+        .collect()
+}
+
+fn package_versions(project: &Project) -> BTreeMap<&str, &str> {
+    project
+        .package_references
+        .iter()
+        .map(|package| (package.name.as_str(), package.version.as_str()))
+        .collect()
+}
+
+fn print_table(report: &Report) {
+    if report.is_empty() {
+        println!("No changes.");
+        return;
+    }
+
+    let print_paths = |title: &str, sign: char, paths: &[PathBuf]| {
+        if !paths.is_empty() {
+            println!("{}:", title);
+            for path in paths.iter() {
+                println!("  {} {}", sign, path.display());
+            }
+        }
+    };
+    let print_references = |title: &str, sign: char, references: &[Reference]| {
+        if !references.is_empty() {
+            println!("{}:", title);
+            for reference in references.iter() {
+                println!(
+                    "  {} {} -> {}",
+                    sign,
+                    reference.from.display(),
+                    reference.to.display()
+                );
+            }
+        }
+    };
+
+    print_paths("Added projects", '+', &report.added_projects);
+    print_paths("Removed projects", '-', &report.removed_projects);
+    print_references("Added references", '+', &report.added_references);
+    print_references("Removed references", '-', &report.removed_references);
+
+    if !report.package_changes.is_empty() {
+        println!("Package changes:");
+        for change in report.package_changes.iter() {
+            println!(
+                "  {}: {} {} -> {}",
+                change.project.display(),
+                change.package,
+                change.before.as_deref().unwrap_or("(none)"),
+                change.after.as_deref().unwrap_or("(none)")
+            );
+        }
+    }
+
+    if !report.framework_changes.is_empty() {
+        println!("Framework changes:");
+        for change in report.framework_changes.iter() {
+            println!(
+                "  {}: [{}] -> [{}]",
+                change.project.display(),
+                change.before.join(", "),
+                change.after.join(", ")
+            );
+        }
+    }
+}
+
+/// Writes the union of both graphs, added projects and references are green and removed ones red.
+fn write_dot<W: std::io::Write>(
+    writer: &mut W,
+    before: &BTreeMap<PathBuf, Project>,
+    after: &BTreeMap<PathBuf, Project>,
+    current_dir: &Path,
+) -> std::io::Result<()> {
+    let paths = before
+        .keys()
+        .chain(after.keys())
+        .map(|path| path.as_path())
+        .collect::<BTreeSet<_>>();
+    let indices = paths
+        .iter()
+        .enumerate()
+        .map(|(index, &path)| (path, index))
+        .collect::<BTreeMap<_, _>>();
+
+    writeln!(writer, "digraph {{")?;
+    writeln!(writer, "  rankdir = \"LR\";")?;
+
+    for (index, &path) in paths.iter().enumerate() {
+        let project = after.get(path).or_else(|| before.get(path)).unwrap();
+        let (color, penwidth) = match (before.contains_key(path), after.contains_key(path)) {
+            (false, true) => (ADDED_COLOR, 3),
+            (true, false) => (REMOVED_COLOR, 3),
+            _ => ("black", 1),
+        };
+        writeln!(
+            writer,
+            "  n{} [label = \"{}\", fillcolor = \"{}\", style = filled, shape = \"{}\", color = \"{}\", penwidth = {}]",
+            index,
+            relative_path(current_dir, path)
+                .display()
+                .to_string()
+                .replace('\\', "\\\\")
+                .replace('"', "\\\""),
+            if project.is_sdk { SDK_COLOR } else { LEGACY_COLOR },
+            if project.is_exe { "box" } else { "ellipse" },
+            color,
+            penwidth
+        )?;
+    }
+
+    let references_before = references(before);
+    let references_after = references(after);
+    for &(from, to) in references_before.union(&references_after) {
+        // References to projects outside of the search path or missing from the revision have no node.
+        let (source, target) = match (indices.get(from), indices.get(to)) {
+            (Some(source), Some(target)) => (source, target),
+            _ => continue,
+        };
+        let attributes = match (
+            references_before.contains(&(from, to)),
+            references_after.contains(&(from, to)),
+        ) {
+            (false, true) => format!(" [color = \"{}\", penwidth = 2]", ADDED_COLOR),
+            (true, false) => format!(
+                " [color = \"{}\", penwidth = 2, style = dashed]",
+                REMOVED_COLOR
+            ),
+            _ => String::new(),
+        };
+        writeln!(writer, "  n{} -> n{}{};", source, target, attributes)?;
+    }
+
+    writeln!(writer, "}}")?;
+
+    Ok(())
+}
@@ -0,0 +1,384 @@
+use log::{debug, warn};
+use regex::Regex;
+use xmltree::{Element, XMLNode};
+
+use crate::csproj::*;
+use crate::path_extensions::*;
+use crate::sln::parse::ParsedSolution;
+use crate::xml_extensions::*;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options<'a> {
+    pub search_path: &'a Path,
+    /// Assign new guids and update all references to them.
+    pub fix: bool,
+}
+
+pub fn guids(options: Options) {
+    let Options { search_path, fix } = options;
+
+    let current_dir = std::env::current_dir().unwrap();
+    let root_dir = crate::discovery::root_dir(search_path).unwrap().root_dir;
+
+    let mut projects = crate::discovery::find_projects(&root_dir);
+    projects.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut projects_by_guid = BTreeMap::<Uuid, Vec<&Project>>::new();
+    for project in projects.iter() {
+        if let Some(guid) = project.project_guid {
+            projects_by_guid.entry(guid).or_default().push(project);
+        }
+    }
+
+    // Maps the path of each project that needs a new guid to its old guid, if any.
+    let mut changes = BTreeMap::<PathBuf, Option<Uuid>>::new();
+
+    for (guid, projects) in projects_by_guid.iter() {
+        if projects.len() < 2 {
+            continue;
+        }
+
+        println!("Duplicate ProjectGuid {{{:X}}}:", guid);
+        for (index, project) in projects.iter().enumerate() {
+            println!(
+                "  {}{}",
+                relative_path(&current_dir, &project.path).display(),
+                if index == 0 { " (kept)" } else { "" }
+            );
+            if index > 0 {
+                changes.insert(project.path.clone(), Some(*guid));
+            }
+        }
+    }
+
+    let missing = projects
+        .iter()
+        .filter(|project| !project.is_sdk && project.project_guid.is_none())
+        .collect::<Vec<_>>();
+
+    if !missing.is_empty() {
+        println!("Missing ProjectGuid:");
+        for project in missing {
+            println!("  {}", relative_path(&current_dir, &project.path).display());
+            changes.insert(project.path.clone(), None);
+        }
+    }
+
+    if changes.is_empty() {
+        println!("No duplicate or missing ProjectGuids found.");
+        return;
+    }
+
+    if !fix {
+        return;
+    }
+
+    let new_guids = changes
+        .keys()
+        .map(|path| {
+            let rel_path = relative_path(&root_dir, path);
+            (
+                path.clone(),
+                crate::sln::guid_from_path(rel_path.to_str().unwrap()),
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    for (path, new_guid) in new_guids.iter() {
+        println!(
+            "Assigning ProjectGuid {{{:X}}} to {}",
+            new_guid,
+            relative_path(&current_dir, path).display()
+        );
+        transform_xml_file(path, |mut root| {
+            set_project_guid(&mut root, *new_guid);
+            Some(root)
+        })
+        .unwrap();
+    }
+
+    for project in projects.iter() {
+        let edited = update_project_reference_guids(&project.path, &changes, &new_guids).unwrap();
+        if edited {
+            println!(
+                "Updated project reference guids in {}",
+                relative_path(&current_dir, &project.path).display()
+            );
+        }
+    }
+
+    for sln_path in crate::sln::find_solutions(&root_dir) {
+        let edited = update_solution_guids(&sln_path, &changes, &new_guids).unwrap();
+        if edited {
+            println!(
+                "Updated project guids in {}",
+                relative_path(&current_dir, &sln_path).display()
+            );
+        }
+    }
+}
+
+fn guid_text(guid: Uuid) -> String {
+    format!("{{{:X}}}", guid)
+}
+
+fn set_text(element: &mut Element, text: String) {
+    element.children = vec![XMLNode::Text(text)];
+}
+
+fn set_project_guid(root: &mut Element, guid: Uuid) {
+    let existing = child_elements_mut(root)
+        .filter(|element| element.name == "PropertyGroup")
+        .flat_map(child_elements_mut)
+        .find(|element| element.name == "ProjectGuid");
+
+    if let Some(element) = existing {
+        set_text(element, guid_text(guid));
+        return;
+    }
+
+    let mut element = Element::new("ProjectGuid");
+    set_text(&mut element, guid_text(guid));
+
+    match root.get_mut_child("PropertyGroup") {
+        Some(property_group) => property_group.children.insert(0, XMLNode::Element(element)),
+        None => {
+            let mut property_group = Element::new("PropertyGroup");
+            property_group.children.push(XMLNode::Element(element));
+            root.children.insert(0, XMLNode::Element(property_group));
+        }
+    }
+}
+
+/// Rewrites the `<Project>` metadata of legacy project references to projects that received a new guid.
+fn update_project_reference_guids(
+    csproj_path: &Path,
+    changes: &BTreeMap<PathBuf, Option<Uuid>>,
+    new_guids: &BTreeMap<PathBuf, Uuid>,
+) -> Result<bool, Error> {
+    let csproj_dir = csproj_path.parent().unwrap();
+
+    let mut edited = false;
+    transform_xml_file(csproj_path, |mut root| {
+        process_tree(&mut root, |element| {
+            if element.name != "ProjectReference" {
+                return;
+            }
+
+            let ref_path = match element.attributes.get("Include") {
+                Some(include) => csproj_dir.join(native_separators(include)).simplify(),
+                None => return,
+            };
+
+            let ref_path = match std::fs::canonicalize(&ref_path) {
+                Ok(ref_path) => ref_path,
+                Err(_) => return,
+            };
+
+            if let (Some(new_guid), Some(project)) =
+                (new_guids.get(&ref_path), element.get_mut_child("Project"))
+            {
+                debug!(
+                    "replacing project reference guid {:?} with {} in {}",
+                    changes.get(&ref_path),
+                    guid_text(*new_guid),
+                    csproj_path.display()
+                );
+                set_text(project, guid_text(*new_guid));
+                edited = true;
+            }
+        });
+
+        if edited {
+            Some(root)
+        } else {
+            None
+        }
+    })?;
+
+    Ok(edited)
+}
+
+/// Replaces the old guids of changed projects in a solution file, leaving the rest of the file untouched.
+fn update_solution_guids(
+    sln_path: &Path,
+    changes: &BTreeMap<PathBuf, Option<Uuid>>,
+    new_guids: &BTreeMap<PathBuf, Uuid>,
+) -> Result<bool, Error> {
+    let solution = ParsedSolution::read(sln_path)?;
+    let mut contents = std::fs::read_to_string(sln_path)?;
+    let mut edited = false;
+
+    for entry in solution.projects() {
+        let entry_path = match std::fs::canonicalize(solution.entry_path(entry)) {
+            Ok(path) => path,
+            Err(_) => continue,
+        };
+
+        let old_guid = match changes.get(&entry_path) {
+            Some(Some(old_guid)) if *old_guid == entry.guid => *old_guid,
+            // Projects without a guid are matched by path, the solution has its own guid for them.
+            Some(None) => entry.guid,
+            _ => continue,
+        };
+        let new_guid = match new_guids.get(&entry_path) {
+            Some(new_guid) if *new_guid != old_guid => *new_guid,
+            _ => continue,
+        };
+
+        let shared = solution
+            .projects()
+            .filter(|other| other.guid == old_guid)
+            .count()
+            > 1;
+
+        if shared {
+            // Only the project line can be attributed to this entry, the configuration lines are ambiguous.
+            warn!(
+                "{} contains multiple projects with guid {}, only updating the entry for {}. Regenerate the solution to fix the project configurations.",
+                sln_path.display(),
+                guid_text(old_guid),
+                entry.path
+            );
+            let project_line_regex = Regex::new(&format!(
+                r#"(?im)^(Project\("[^"]*"\)\s*=\s*"[^"]*"\s*,\s*"{}"\s*,\s*"){}"#,
+                regex::escape(&entry.path),
+                regex::escape(&guid_text(old_guid))
+            ))
+            .unwrap();
+            contents = project_line_regex
+                .replace(&contents, format!("${{1}}{}", guid_text(new_guid)).as_str())
+                .into_owned();
+        } else {
+            let old_guid_regex =
+                Regex::new(&format!("(?i){}", regex::escape(&guid_text(old_guid)))).unwrap();
+            contents = old_guid_regex
+                .replace_all(&contents, guid_text(new_guid).as_str())
+                .into_owned();
+        }
+
+        edited = true;
+    }
+
+    if edited {
+        std::fs::write(sln_path, contents)?;
+    }
+
+    Ok(edited)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD_GUID: &str = "{5A6C8E0B-1D2F-4A3B-8C9D-0E1F2A3B4C5D}";
+    const NEW_GUID: &str = "{0B2D1F6E-3A8C-4F43-9C3B-2F1E6C9A7D10}";
+
+    fn guid(text: &str) -> Uuid {
+        Uuid::parse_str(text.trim_matches(|c| c == '{' || c == '}')).unwrap()
+    }
+
+    fn legacy_project(guid: Option<&str>, references: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<Project ToolsVersion="15.0" xmlns="http://schemas.microsoft.com/developer/msbuild/2003">
+  <PropertyGroup>
+    {}<OutputType>Library</OutputType>
+  </PropertyGroup>
+  <ItemGroup>{}</ItemGroup>
+</Project>
+"#,
+            guid.map(|guid| format!("<ProjectGuid>{}</ProjectGuid>", guid))
+                .unwrap_or_default(),
+            references
+        )
+    }
+
+    /// Creates `Lib/Lib.csproj` without a guid and `App/App.csproj` referencing it, returns the canonical directory.
+    fn write_projects(dir: &Path) -> PathBuf {
+        let dir = std::fs::canonicalize(dir).unwrap();
+        for name in ["Lib", "App"] {
+            std::fs::create_dir(dir.join(name)).unwrap();
+        }
+        std::fs::write(dir.join("Lib/Lib.csproj"), legacy_project(None, "")).unwrap();
+        std::fs::write(
+            dir.join("App/App.csproj"),
+            legacy_project(
+                Some(OLD_GUID),
+                &format!(
+                    r#"<ProjectReference Include="..\Lib\Lib.csproj"><Project>{}</Project></ProjectReference>"#,
+                    OLD_GUID
+                ),
+            ),
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn set_project_guid_adds_missing_guid() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = write_projects(dir.path());
+        let lib_path = dir.join("Lib/Lib.csproj");
+
+        transform_xml_file(&lib_path, |mut root| {
+            set_project_guid(&mut root, guid(NEW_GUID));
+            Some(root)
+        })
+        .unwrap();
+
+        let project = read_and_parse_project(lib_path).unwrap();
+        assert_eq!(project.project_guid, Some(guid(NEW_GUID)));
+    }
+
+    #[test]
+    fn update_project_reference_guids_rewrites_project_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = write_projects(dir.path());
+        let lib_path = dir.join("Lib/Lib.csproj");
+        let app_path = dir.join("App/App.csproj");
+
+        let changes = BTreeMap::from([(lib_path.clone(), None)]);
+        let new_guids = BTreeMap::from([(lib_path, guid(NEW_GUID))]);
+        assert!(update_project_reference_guids(&app_path, &changes, &new_guids).unwrap());
+
+        let contents = std::fs::read_to_string(&app_path).unwrap();
+        assert!(contents.contains(&format!("<Project>{}</Project>", NEW_GUID)));
+        // The guid of the referencing project itself is left alone.
+        assert!(contents.contains(&format!("<ProjectGuid>{}</ProjectGuid>", OLD_GUID)));
+    }
+
+    #[test]
+    fn update_solution_guids_matches_projects_without_guid_by_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = write_projects(dir.path());
+        let lib_path = dir.join("Lib/Lib.csproj");
+        let sln_path = dir.join("All.sln");
+        std::fs::write(
+            &sln_path,
+            format!(
+                "Project(\"{{FAE04EC0-301F-11D3-BF4B-00C04F79EFBC}}\") = \"Lib\", \"Lib\\Lib.csproj\", \"{0}\"
+EndProject
+Global
+\tGlobalSection(ProjectConfigurationPlatforms) = postSolution
+\t\t{0}.Debug|Any CPU.ActiveCfg = Debug|Any CPU
+\tEndGlobalSection
+EndGlobal
+",
+                OLD_GUID
+            ),
+        )
+        .unwrap();
+
+        let changes = BTreeMap::from([(lib_path.clone(), None)]);
+        let new_guids = BTreeMap::from([(lib_path, guid(NEW_GUID))]);
+        assert!(update_solution_guids(&sln_path, &changes, &new_guids).unwrap());
+
+        let contents = std::fs::read_to_string(&sln_path).unwrap();
+        assert!(!contents.contains(OLD_GUID));
+        assert_eq!(contents.matches(NEW_GUID).count(), 2);
+    }
+}
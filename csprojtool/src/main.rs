@@ -1,15 +1,31 @@
+mod affected;
+mod build_order;
+mod check_architecture;
+mod check_frameworks;
 mod cli;
+mod coverage;
 mod csproj;
+mod cycles;
 mod dependency_graph;
+mod discovery;
+mod graph;
+mod graph_diff;
+mod guids;
 mod list;
+mod metrics;
 mod move_command;
+mod package_conflicts;
 mod path_extensions;
 mod post_migration_cleanup;
+mod prune_references;
 mod utils;
+mod why;
 mod xml_extensions;
 pub use dependency_graph::*;
 pub use post_migration_cleanup::*;
 mod sln;
+mod sln_per_exe;
+mod tfm;
 
 use std::path::{Path, PathBuf};
 
@@ -23,6 +39,10 @@ fn get_search_path(matches: &clap::ArgMatches) -> PathBuf {
     Path::new(search_path).components().collect()
 }
 
+fn get_output_format(matches: &clap::ArgMatches) -> utils::OutputFormat {
+    matches.value_of(cli::ARG_FORMAT).unwrap().parse().unwrap()
+}
+
 fn main() {
     ::pretty_env_logger::init();
 
@@ -30,11 +50,41 @@ fn main() {
     let matches = app.get_matches();
 
     if let Some(matches) = matches.subcommand_matches(cli::CMD_DEPENDENCY_GRAPH) {
-        let glob = matches.value_of(cli::ARG_GLOB).unwrap();
-        let search = matches.value_of(cli::ARG_SEARCH_PATH).unwrap();
-        let dot = matches.value_of(cli::ARG_DOT);
-        let json = matches.value_of(cli::ARG_JSON);
-        dependency_graph(glob, search, dot, json);
+        let mut exclude = globset::GlobSetBuilder::new();
+        for pattern in matches.values_of(cli::ARG_EXCLUDE).into_iter().flatten() {
+            exclude.add(globset::Glob::new(pattern).unwrap());
+        }
+        let ego = matches.value_of(cli::ARG_EGO).map(Path::new);
+        dependency_graph(dependency_graph::Options {
+            glob: matches.value_of(cli::ARG_GLOB).unwrap(),
+            search: matches.value_of(cli::ARG_SEARCH_PATH).unwrap(),
+            dot: matches.value_of(cli::ARG_DOT),
+            dot_options: dependency_graph::dot::DotOptions {
+                clusters: matches.is_present(cli::ARG_CLUSTERS),
+                collapse_depth: matches
+                    .value_of(cli::ARG_COLLAPSE)
+                    .map(|depth| depth.parse().unwrap()),
+            },
+            json: matches.value_of(cli::ARG_JSON),
+            mermaid: matches.value_of(cli::ARG_MERMAID),
+            graphml: matches.value_of(cli::ARG_GRAPHML),
+            plantuml: matches.value_of(cli::ARG_PLANTUML),
+            html: matches.value_of(cli::ARG_HTML),
+            packages: matches.is_present(cli::ARG_PACKAGES),
+            package_family_depth: matches
+                .value_of(cli::ARG_PACKAGE_FAMILIES)
+                .map(|depth| depth.parse().unwrap()),
+            fail_on_cycle: matches.is_present(cli::ARG_FAIL_ON_CYCLE),
+            exclude: &exclude.build().unwrap(),
+            depth: matches
+                .value_of(cli::ARG_DEPTH)
+                .map(|depth| depth.parse().unwrap()),
+            ego,
+            outgoing: !matches.is_present(cli::ARG_ONLY_INCOMING),
+            // Without a direction, glob seeds only follow their references and the ego graph follows both.
+            incoming: matches.is_present(cli::ARG_ONLY_INCOMING)
+                || (ego.is_some() && !matches.is_present(cli::ARG_ONLY_OUTGOING)),
+        });
     }
 
     if let Some(matches) = matches.subcommand_matches(cli::CMD_POST_MIGRATION_CLEANUP) {
@@ -57,6 +107,17 @@ fn main() {
         });
     }
 
+    if let Some(matches) = matches.subcommand_matches(cli::CMD_BUILD_ORDER) {
+        build_order::run(build_order::Options {
+            search_path: &get_search_path(&matches),
+            follow_incoming_project_references: !matches
+                .is_present(cli::ARG_DO_NOT_FOLLOW_INCOMING_PROJECT_REFERENCES),
+            follow_outgoing_project_references: !matches
+                .is_present(cli::ARG_DO_NOT_FOLLOW_OUTGOING_PROJECT_REFERENCES),
+            format: get_output_format(&matches),
+        });
+    }
+
     if let Some(matches) = matches.subcommand_matches(cli::CMD_SLN) {
         sln::sln(sln::Options {
             sln_path: &std::path::PathBuf::from(matches.value_of(cli::ARG_SLN_PATH).unwrap()),
@@ -65,6 +126,131 @@ fn main() {
                 .is_present(cli::ARG_DO_NOT_FOLLOW_INCOMING_PROJECT_REFERENCES),
             follow_outgoing_project_references: !matches
                 .is_present(cli::ARG_DO_NOT_FOLLOW_OUTGOING_PROJECT_REFERENCES),
+            keep_guids: matches.is_present(cli::ARG_KEEP_GUIDS),
+            build_dependencies_path: matches
+                .value_of_os(cli::ARG_BUILD_DEPENDENCIES)
+                .map(Path::new),
+            visual_studio_version: matches.value_of(cli::ARG_VS_VERSION),
+        });
+    }
+
+    if let Some(matches) = matches.subcommand_matches(cli::CMD_SLN_PER_EXE) {
+        sln_per_exe::sln_per_exe(sln_per_exe::Options {
+            search_path: &get_search_path(&matches),
+            out_dir: Path::new(matches.value_of(cli::ARG_OUT_DIR).unwrap()),
+            name_template: matches.value_of(cli::ARG_NAME_TEMPLATE).unwrap(),
+            keep_guids: matches.is_present(cli::ARG_KEEP_GUIDS),
+            build_dependencies_path: matches
+                .value_of_os(cli::ARG_BUILD_DEPENDENCIES)
+                .map(Path::new),
+            visual_studio_version: matches.value_of(cli::ARG_VS_VERSION),
+        });
+    }
+
+    if let Some(matches) = matches.subcommand_matches(cli::CMD_SLN_COVERAGE) {
+        coverage::run(coverage::Options {
+            search_path: &get_search_path(&matches),
+            format: get_output_format(&matches),
+        });
+    }
+
+    if let Some(matches) = matches.subcommand_matches(cli::CMD_CYCLES) {
+        cycles::run(cycles::Options {
+            search_path: &get_search_path(&matches),
+            format: get_output_format(&matches),
+            fail_on_cycle: matches.is_present(cli::ARG_FAIL_ON_CYCLE),
+        });
+    }
+
+    if let Some(matches) = matches.subcommand_matches(cli::CMD_PRUNE_REFERENCES) {
+        let mut keep = globset::GlobSetBuilder::new();
+        for pattern in matches.values_of(cli::ARG_KEEP).into_iter().flatten() {
+            keep.add(globset::Glob::new(pattern).unwrap());
+        }
+        prune_references::prune_references(prune_references::Options {
+            search_path: &get_search_path(&matches),
+            dry_run: matches.is_present(cli::ARG_DRY_RUN),
+            keep: &keep.build().unwrap(),
+        });
+    }
+
+    if let Some(matches) = matches.subcommand_matches(cli::CMD_GUIDS) {
+        guids::guids(guids::Options {
+            search_path: &get_search_path(&matches),
+            fix: matches.is_present(cli::ARG_FIX),
+        });
+    }
+
+    if let Some(matches) = matches.subcommand_matches(cli::CMD_PACKAGE_CONFLICTS) {
+        let versions = matches
+            .values_of(cli::ARG_VERSION)
+            .into_iter()
+            .flatten()
+            .map(|value| {
+                let (package, version) = value
+                    .split_once('=')
+                    .expect("Expected --version PACKAGE=VERSION");
+                (package.to_lowercase(), version.to_string())
+            })
+            .collect();
+        package_conflicts::run(package_conflicts::Options {
+            search_path: &get_search_path(&matches),
+            format: get_output_format(&matches),
+            fix: matches.is_present(cli::ARG_FIX),
+            versions: &versions,
+        });
+    }
+
+    if let Some(matches) = matches.subcommand_matches(cli::CMD_AFFECTED) {
+        affected::run(affected::Options {
+            search_path: &get_search_path(&matches),
+            base: matches.value_of(cli::ARG_BASE).unwrap(),
+            tests_only: matches.is_present(cli::ARG_TESTS_ONLY),
+            exes_only: matches.is_present(cli::ARG_EXES_ONLY),
+            format: get_output_format(&matches),
+        });
+    }
+
+    if let Some(matches) = matches.subcommand_matches(cli::CMD_CHECK_ARCHITECTURE) {
+        check_architecture::run(check_architecture::Options {
+            config_path: Path::new(matches.value_of(cli::ARG_CONFIG).unwrap()),
+            search_path: &get_search_path(&matches),
+            format: get_output_format(&matches),
+        });
+    }
+
+    if let Some(matches) = matches.subcommand_matches(cli::CMD_CHECK_FRAMEWORKS) {
+        check_frameworks::run(check_frameworks::Options {
+            search_path: &get_search_path(&matches),
+            format: get_output_format(&matches),
+        });
+    }
+
+    if let Some(matches) = matches.subcommand_matches(cli::CMD_METRICS) {
+        metrics::run(metrics::Options {
+            glob: matches.value_of(cli::ARG_GLOB).unwrap(),
+            search: matches.value_of(cli::ARG_SEARCH_PATH).unwrap(),
+            top: matches.value_of(cli::ARG_TOP).unwrap().parse().unwrap(),
+            format: get_output_format(&matches),
+        });
+    }
+
+    if let Some(matches) = matches.subcommand_matches(cli::CMD_GRAPH_DIFF) {
+        graph_diff::run(graph_diff::Options {
+            search_path: &get_search_path(&matches),
+            rev_a: matches.value_of(cli::ARG_REV_A).unwrap(),
+            rev_b: matches.value_of(cli::ARG_REV_B).unwrap(),
+            dot: matches.value_of(cli::ARG_DOT),
+            format: get_output_format(&matches),
+        });
+    }
+
+    if let Some(matches) = matches.subcommand_matches(cli::CMD_WHY) {
+        why::run(why::Options {
+            from: Path::new(matches.value_of(cli::ARG_FROM).unwrap()),
+            to: matches.value_of(cli::ARG_TO).unwrap(),
+            limit: cli::parse_limit(matches.value_of(cli::ARG_LIMIT)),
+            format: get_output_format(&matches),
         });
     }
 
@@ -0,0 +1,234 @@
+use serde::Serialize;
+
+use crate::dependency_graph::model::{Graph, NodeKind};
+use crate::utils::OutputFormat;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options<'a> {
+    pub glob: &'a str,
+    pub search: &'a str,
+    /// The number of hubs to report.
+    pub top: usize,
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub projects: Vec<ProjectMetrics>,
+    pub summary: Summary,
+    /// The projects with the highest fan-in, most referenced first.
+    pub hubs: Vec<PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectMetrics {
+    pub path: PathBuf,
+    /// The number of projects referencing this project.
+    pub fan_in: usize,
+    /// The number of projects this project references.
+    pub fan_out: usize,
+    /// The number of projects this project depends on directly or indirectly.
+    pub closure_size: usize,
+    /// The smallest number of references from an executable to this project, if any executable depends on it.
+    pub depth: Option<usize>,
+    /// Fan-out divided by the sum of fan-in and fan-out, from 0 (stable) to 1 (unstable).
+    pub instability: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    pub projects: usize,
+    pub references: usize,
+    pub executables: usize,
+    /// Projects that no executable depends on, excluding the executables themselves.
+    pub unreachable_from_executables: usize,
+    pub max_depth: Option<usize>,
+    pub average_fan_out: f64,
+    pub average_closure_size: f64,
+}
+
+pub fn run(options: Options) {
+    let report = metrics(options);
+
+    match options.format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(std::io::stdout(), &report).unwrap();
+            println!();
+        }
+        OutputFormat::Table => print_table(&report),
+    }
+}
+
+pub fn metrics(options: Options) -> Report {
+    let Options {
+        glob, search, top, ..
+    } = options;
+
+    let projects = crate::dependency_graph::load_projects(glob, search);
+    let graph = Graph::new(&projects);
+    let n = graph.nodes.len();
+
+    let edges = graph
+        .edges
+        .iter()
+        .map(|edge| (edge.source, edge.target))
+        .collect::<Vec<_>>();
+    let outgoing = crate::graph::outgoing_edges(n, &edges);
+
+    let mut fan_in = vec![0; n];
+    for &(_, target) in edges.iter() {
+        fan_in[target] += 1;
+    }
+
+    // Breadth first search from all executables at once yields the shortest distances.
+    let mut depth = vec![None; n];
+    let mut queue = VecDeque::new();
+    for (index, node) in graph.nodes.iter().enumerate() {
+        if node.kind == NodeKind::Executable {
+            depth[index] = Some(0);
+            queue.push_back(index);
+        }
+    }
+    while let Some(index) = queue.pop_front() {
+        for &target in outgoing[index].iter() {
+            if depth[target].is_none() {
+                depth[target] = depth[index].map(|depth| depth + 1);
+                queue.push_back(target);
+            }
+        }
+    }
+
+    let projects = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node)| {
+            let fan_out = outgoing[index].len();
+            ProjectMetrics {
+                path: node.path.clone(),
+                fan_in: fan_in[index],
+                fan_out,
+                closure_size: crate::graph::reachable(&outgoing, index)
+                    .into_iter()
+                    .enumerate()
+                    .filter(|&(target, reachable)| reachable && target != index)
+                    .count(),
+                depth: depth[index],
+                instability: if fan_in[index] + fan_out == 0 {
+                    None
+                } else {
+                    Some(fan_out as f64 / (fan_in[index] + fan_out) as f64)
+                },
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let average = |total: usize| {
+        if projects.is_empty() {
+            0.0
+        } else {
+            total as f64 / projects.len() as f64
+        }
+    };
+
+    let summary = Summary {
+        projects: projects.len(),
+        references: edges.len(),
+        executables: graph
+            .nodes
+            .iter()
+            .filter(|node| node.kind == NodeKind::Executable)
+            .count(),
+        unreachable_from_executables: projects
+            .iter()
+            .filter(|project| project.depth.is_none())
+            .count(),
+        max_depth: projects.iter().filter_map(|project| project.depth).max(),
+        average_fan_out: average(projects.iter().map(|project| project.fan_out).sum()),
+        average_closure_size: average(projects.iter().map(|project| project.closure_size).sum()),
+    };
+
+    let mut hubs = projects.iter().collect::<Vec<_>>();
+    hubs.sort_by(|a, b| {
+        b.fan_in
+            .cmp(&a.fan_in)
+            .then(b.closure_size.cmp(&a.closure_size))
+    });
+    let hubs = hubs
+        .into_iter()
+        .filter(|project| project.fan_in > 0)
+        .take(top)
+        .map(|project| project.path.clone())
+        .collect();
+
+    Report {
+        projects,
+        summary,
+        hubs,
+    }
+}
+
+fn print_table(report: &Report) {
+    let width = report
+        .projects
+        .iter()
+        .map(|project| project.path.display().to_string().len())
+        .max()
+        .unwrap_or_default();
+
+    println!(
+        "  {:width$} {:>6} {:>7} {:>7} {:>5} {:>11}",
+        "project",
+        "fan-in",
+        "fan-out",
+        "closure",
+        "depth",
+        "instability",
+        width = width
+    );
+    for project in report.projects.iter() {
+        println!(
+            "  {:width$} {:>6} {:>7} {:>7} {:>5} {:>11}",
+            project.path.display().to_string(),
+            project.fan_in,
+            project.fan_out,
+            project.closure_size,
+            project
+                .depth
+                .map(|depth| depth.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            project
+                .instability
+                .map(|instability| format!("{:.2}", instability))
+                .unwrap_or_else(|| "-".to_string()),
+            width = width
+        );
+    }
+
+    let summary = &report.summary;
+    println!();
+    println!("Projects: {}", summary.projects);
+    println!("References: {}", summary.references);
+    println!("Executables: {}", summary.executables);
+    println!(
+        "Unreachable from executables: {}",
+        summary.unreachable_from_executables
+    );
+    println!(
+        "Max depth: {}",
+        summary
+            .max_depth
+            .map(|depth| depth.to_string())
+            .unwrap_or_else(|| "-".to_string())
+    );
+    println!("Average fan-out: {:.2}", summary.average_fan_out);
+    println!("Average closure size: {:.2}", summary.average_closure_size);
+
+    println!();
+    println!("Hubs:");
+    for path in report.hubs.iter() {
+        println!("  {}", path.display());
+    }
+}
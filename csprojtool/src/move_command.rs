@@ -1,5 +1,6 @@
 use std::{
     ffi::{OsStr, OsString},
+    fmt,
     path::{Path, PathBuf},
     process::Command,
 };
@@ -8,19 +9,86 @@ use log::{debug, info};
 use xmltree::{Element, XMLNode};
 
 use crate::{
+    discovery::find_project_files,
     path_extensions::{relative_path, PathExt},
     utils::{find_dir_csproj, find_git_root},
-    xml_extensions::{child_elements, depth_first_visit_nodes, process_tree, transform_xml_file},
+    xml_extensions::{
+        child_elements, depth_first_visit_nodes, process_tree, read_xml_file, write_xml_file,
+        xml_to_bytes,
+    },
 };
 
 const ARG_FROM: &'static str = "from";
 const ARG_TO: &'static str = "to";
+const ARG_DRY_RUN: &'static str = "dry-run";
+const ARG_DIFF: &'static str = "diff";
 const CMD_MOVE: &'static str = "mv";
 
 #[derive(Debug)]
 pub struct MoveCommand {
     old: PathBuf,
     new: PathBuf,
+    dry_run: bool,
+    diff: bool,
+}
+
+/// A single change made by moving a project.
+#[derive(Debug)]
+enum Step {
+    MoveDirectory {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    RenameProject {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    RewriteProjectReference {
+        project: PathBuf,
+        from: String,
+        to: String,
+    },
+    RewriteRelativePath {
+        from: String,
+        to: String,
+    },
+    AddProperty {
+        name: &'static str,
+        value: String,
+    },
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cur_dir = std::env::current_dir().unwrap();
+        let display = |path: &Path| relative_path(&cur_dir, path).display().to_string();
+        match self {
+            Step::MoveDirectory { from, to } => {
+                write!(f, "move directory {} to {}", display(from), display(to))
+            }
+            Step::RenameProject { from, to } => {
+                write!(f, "rename project {} to {}", display(from), display(to))
+            }
+            Step::RewriteProjectReference { project, from, to } => write!(
+                f,
+                "rewrite project reference {} to {} in {}",
+                from,
+                to,
+                display(project)
+            ),
+            Step::RewriteRelativePath { from, to } => {
+                write!(f, "rewrite relative path {} to {}", from, to)
+            }
+            Step::AddProperty { name, value } => write!(f, "add {} {}", name, value),
+        }
+    }
+}
+
+/// A project file that has to be rewritten, read from its path before the move and written to its path after it.
+struct Edit {
+    old_path: PathBuf,
+    new_path: PathBuf,
+    root: Element,
 }
 
 impl MoveCommand {
@@ -46,6 +114,21 @@ impl MoveCommand {
                     .takes_value(true)
                     .index(2),
             )
+            .arg(
+                Arg::with_name(ARG_DRY_RUN)
+                    .long("dry-run")
+                    .takes_value(false)
+                    .help("Only print the changes that would be made"),
+            )
+            .arg(
+                Arg::with_name(ARG_DIFF)
+                    .long("diff")
+                    .takes_value(false)
+                    .requires(ARG_DRY_RUN)
+                    .help(
+                        "Also print a unified diff of every project file that would be rewritten",
+                    ),
+            )
     }
 
     pub fn try_from_matches(matches: &clap::ArgMatches) -> Option<Self> {
@@ -56,6 +139,8 @@ impl MoveCommand {
         Self {
             old: matches.value_of_os(ARG_FROM).unwrap().into(),
             new: matches.value_of_os(ARG_TO).unwrap().into(),
+            dry_run: matches.is_present(ARG_DRY_RUN),
+            diff: matches.is_present(ARG_DIFF),
         }
     }
 
@@ -127,26 +212,7 @@ impl MoveCommand {
 
         debug!("root: {}", root.display());
 
-        let csproj_matcher = globset::GlobBuilder::new("*.csproj")
-            .build()
-            .unwrap()
-            .compile_matcher();
-        let csproj_paths = ignore::WalkBuilder::new(root)
-            .build()
-            .filter_map(|entry| match entry {
-                Ok(e) => {
-                    if e.file_type().map(|t| t.is_file()).unwrap_or_default()
-                        && csproj_matcher.is_match(e.path())
-                    {
-                        Some(Ok(e.path().to_owned()))
-                    } else {
-                        None
-                    }
-                }
-                Err(e) => Some(Err(e)),
-            })
-            .collect::<Result<Vec<_>, ignore::Error>>()
-            .unwrap();
+        let csproj_paths = find_project_files(root);
 
         // Check for nested projects
         let nested = csproj_paths
@@ -160,26 +226,20 @@ impl MoveCommand {
             );
         }
 
-        // Move the files
-        let mut mv_dir = Command::new("git");
-        mv_dir.args(&[OsStr::new("mv"), old_dir.as_os_str(), new_dir.as_os_str()]);
-        debug!("{:?}", &mv_dir);
-        mv_dir.output().expect("failed to move files");
-
-        {
-            let current_path = new_dir.join(old_file.file_name().unwrap());
-            if &current_path != &new_file {
-                let mut mv_file = Command::new("git");
-                mv_file.args(&[
-                    OsStr::new("mv"),
-                    current_path.as_os_str(),
-                    new_file.as_os_str(),
-                ]);
-                debug!("{:?}", &mv_file);
-                mv_file.output().expect("failed to move files");
-            }
+        let mut steps = vec![Step::MoveDirectory {
+            from: old_dir.clone(),
+            to: new_dir.clone(),
+        }];
+        let moved_file = new_dir.join(old_file.file_name().unwrap());
+        if moved_file != new_file {
+            steps.push(Step::RenameProject {
+                from: moved_file.clone(),
+                to: new_file.clone(),
+            });
         }
 
+        let mut edits = Vec::new();
+
         for csproj_path in csproj_paths.iter() {
             if csproj_path == &old_file {
                 continue;
@@ -187,112 +247,177 @@ impl MoveCommand {
 
             let csproj_dir = csproj_path.parent().unwrap();
 
+            let mut root = read_xml_file(csproj_path).unwrap();
             let mut edited = false;
-            transform_xml_file(csproj_path, |mut root| {
-                process_tree(&mut root, |element| match element.name.as_ref() {
-                    "ProjectReference" => {
-                        if let Some(include) = element.attributes.get_mut("Include") {
-                            let ref_path = [csproj_dir, Path::new(include)]
-                                .iter()
-                                .collect::<PathBuf>()
-                                .simplify();
-
-                            if ref_path == old_file {
-                                let new_ref = relative_path(csproj_dir, &new_file);
-                                debug!(
-                                    "replacing project reference {} with {} in {}",
-                                    include,
-                                    new_ref.display(),
-                                    csproj_path.display()
-                                );
-                                *include = new_ref.to_str().unwrap().to_owned();
-                                edited = true;
-                            }
+            process_tree(&mut root, |element| match element.name.as_ref() {
+                "ProjectReference" => {
+                    if let Some(include) = element.attributes.get_mut("Include") {
+                        let ref_path = [csproj_dir, Path::new(include)]
+                            .iter()
+                            .collect::<PathBuf>()
+                            .simplify();
+
+                        if ref_path == old_file {
+                            let new_ref = relative_path(csproj_dir, &new_file);
+                            debug!(
+                                "replacing project reference {} with {} in {}",
+                                include,
+                                new_ref.display(),
+                                csproj_path.display()
+                            );
+                            let new_ref = new_ref.to_str().unwrap().to_owned();
+                            steps.push(Step::RewriteProjectReference {
+                                project: csproj_path.clone(),
+                                from: std::mem::replace(include, new_ref.clone()),
+                                to: new_ref,
+                            });
+                            edited = true;
                         }
                     }
-                    _ => {}
-                });
-
-                if edited {
-                    Some(root)
-                } else {
-                    None
                 }
-            })
-            .unwrap();
+                _ => {}
+            });
 
             if edited {
-                let mut add_file = Command::new("git");
-                add_file.args(&[OsStr::new("add"), csproj_path.as_os_str()]);
-                debug!("{:?}", &add_file);
-                add_file.output().expect("failed to add file");
+                edits.push(Edit {
+                    old_path: csproj_path.clone(),
+                    new_path: csproj_path.clone(),
+                    root,
+                });
             }
         }
 
+        // The moved project is read before the move, relative paths that point into its own directory are left as is.
         let mut edited = false;
-
-        transform_xml_file(&new_file, |root| {
-            let mut root_node = XMLNode::Element(root);
-
-            depth_first_visit_nodes(&mut root_node, |node| match node {
-                XMLNode::Element(element) => match element.name.as_ref() {
-                    "Project" => {
-                        let name = old_file.file_stem().unwrap().to_str().unwrap();
-                        edited |= ensure_root_namespace_and_assembly_name(element, name);
-                    }
-                    _ => {
-                        for (_, val) in element.attributes.iter_mut() {
-                            edited |= try_rewrite_relative_path(val, &old_dir, &new_dir);
-                        }
+        let mut root_node = XMLNode::Element(read_xml_file(&old_file).unwrap());
+        depth_first_visit_nodes(&mut root_node, |node| match node {
+            XMLNode::Element(element) => match element.name.as_ref() {
+                "Project" => {
+                    let name = old_file.file_stem().unwrap().to_str().unwrap();
+                    edited |= ensure_root_namespace_and_assembly_name(element, name, &mut steps);
+                }
+                _ => {
+                    for (_, val) in element.attributes.iter_mut() {
+                        edited |= try_rewrite_relative_path(val, &old_dir, &new_dir, &mut steps);
                     }
-                },
-                XMLNode::Text(text) => {
-                    edited |= try_rewrite_relative_path(text, &old_dir, &new_dir);
                 }
-                _ => {}
-            });
+            },
+            XMLNode::Text(text) => {
+                edited |= try_rewrite_relative_path(text, &old_dir, &new_dir, &mut steps);
+            }
+            _ => {}
+        });
 
+        if edited {
             let root = match root_node {
                 XMLNode::Element(root) => root,
                 _ => unreachable!(),
             };
+            edits.push(Edit {
+                old_path: old_file.clone(),
+                new_path: new_file.clone(),
+                root,
+            });
+        }
 
-            if edited {
-                Some(root)
-            } else {
-                None
+        if self.dry_run {
+            for step in steps.iter() {
+                println!("{}", step);
             }
-        })
-        .unwrap();
+            if self.diff {
+                for edit in edits.iter() {
+                    print!("{}", diff(edit, root));
+                }
+            }
+            return;
+        }
+
+        // Move the files
+        let mut mv_dir = Command::new("git");
+        mv_dir.args(&[OsStr::new("mv"), old_dir.as_os_str(), new_dir.as_os_str()]);
+        debug!("{:?}", &mv_dir);
+        mv_dir.output().expect("failed to move files");
+
+        if moved_file != new_file {
+            let mut mv_file = Command::new("git");
+            mv_file.args(&[
+                OsStr::new("mv"),
+                moved_file.as_os_str(),
+                new_file.as_os_str(),
+            ]);
+            debug!("{:?}", &mv_file);
+            mv_file.output().expect("failed to move files");
+        }
+
+        for edit in edits.iter() {
+            write_xml_file(&edit.new_path, &edit.root).unwrap();
 
-        if edited {
             let mut add_file = Command::new("git");
-            add_file.args(&[OsStr::new("add"), new_file.as_os_str()]);
+            add_file.args(&[OsStr::new("add"), edit.new_path.as_os_str()]);
             debug!("{:?}", &add_file);
             add_file.output().expect("failed to add file");
         }
     }
 }
 
-fn try_rewrite_relative_path(val: &mut String, old_dir: &Path, new_dir: &Path) -> bool {
+/// Computes a unified diff between the file before the move and its rewritten contents with `git diff --no-index`.
+/// Both versions are written to a temporary directory as `a/<old path>` and `b/<new path>`, relative to the root
+/// directory, so the diff headers carry the project paths without any rewriting.
+fn diff(edit: &Edit, root: &Path) -> String {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let old_path = Path::new("a").join(relative_path(root, &edit.old_path));
+    let new_path = Path::new("b").join(relative_path(root, &edit.new_path));
+    for path in [&old_path, &new_path] {
+        std::fs::create_dir_all(temp_dir.path().join(path).parent().unwrap()).unwrap();
+    }
+    std::fs::copy(&edit.old_path, temp_dir.path().join(&old_path)).unwrap();
+    std::fs::write(temp_dir.path().join(&new_path), xml_to_bytes(&edit.root)).unwrap();
+
+    let mut git_diff = Command::new("git");
+    git_diff
+        .current_dir(temp_dir.path())
+        .args([
+            "diff",
+            "--no-index",
+            "--no-color",
+            "--no-ext-diff",
+            "--no-prefix",
+            "--",
+        ])
+        .args([old_path.as_os_str(), new_path.as_os_str()]);
+    debug!("{:?}", &git_diff);
+    // Exits with 1 when the files differ.
+    let output = git_diff.output().expect("failed to diff files");
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn try_rewrite_relative_path(
+    val: &mut String,
+    old_dir: &Path,
+    new_dir: &Path,
+    steps: &mut Vec<Step>,
+) -> bool {
     if !looks_like_out_of_tree_relative_path(val) {
         return false;
     }
 
-    let mut edited = true;
+    let mut edited = false;
     let path = Path::new(val);
     if !path.has_root() {
         let path = path.simplify();
         let old_abs_path = old_dir.join(&path).simplify();
         match std::fs::metadata(&old_abs_path) {
-            Ok(_) => {
+            Ok(_) if !old_abs_path.starts_with(old_dir) => {
                 let new_rel_path = relative_path(&new_dir, &old_abs_path);
-                debug!(
-                    "rewriting relative path from {} to {}",
-                    val,
-                    new_rel_path.display(),
-                );
-                *val = new_rel_path.to_str().unwrap().to_owned();
+                let new_rel_path = new_rel_path.to_str().unwrap().to_owned();
+                if &new_rel_path == val {
+                    return false;
+                }
+                debug!("rewriting relative path from {} to {}", val, new_rel_path);
+                steps.push(Step::RewriteRelativePath {
+                    from: std::mem::replace(val, new_rel_path.clone()),
+                    to: new_rel_path,
+                });
                 edited = true;
             }
             _ => {}
@@ -308,7 +433,11 @@ fn looks_like_out_of_tree_relative_path(val: &str) -> bool {
     RE.is_match(val)
 }
 
-fn ensure_root_namespace_and_assembly_name(element: &mut xmltree::Element, name: &str) -> bool {
+fn ensure_root_namespace_and_assembly_name(
+    element: &mut xmltree::Element,
+    name: &str,
+    steps: &mut Vec<Step>,
+) -> bool {
     let (root_namespace, assembly_name) =
         child_elements(element).fold((None, None), |state, element| {
             if element.name == "PropertyGroup" {
@@ -349,6 +478,10 @@ fn ensure_root_namespace_and_assembly_name(element: &mut xmltree::Element, name:
             let mut el = Element::new("RootNamespace");
             el.children.push(XMLNode::Text(name.to_owned()));
             property_group_element.children.push(XMLNode::Element(el));
+            steps.push(Step::AddProperty {
+                name: "RootNamespace",
+                value: name.to_owned(),
+            });
             modified = true;
         }
 
@@ -357,6 +490,10 @@ fn ensure_root_namespace_and_assembly_name(element: &mut xmltree::Element, name:
             let mut el = Element::new("AssemblyName");
             el.children.push(XMLNode::Text(name.to_owned()));
             property_group_element.children.push(XMLNode::Element(el));
+            steps.push(Step::AddProperty {
+                name: "AssemblyName",
+                value: name.to_owned(),
+            });
             modified = true;
         }
     }
@@ -0,0 +1,395 @@
+use log::{debug, warn};
+use serde::Serialize;
+
+use crate::csproj::*;
+use crate::discovery::*;
+use crate::path_extensions::*;
+use crate::utils::OutputFormat;
+use crate::xml_extensions::*;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options<'a> {
+    pub search_path: &'a Path,
+    pub format: OutputFormat,
+    /// Align the conflicting package references to a single version.
+    pub fix: bool,
+    /// Maps lower case package names to the version to align to, other packages are aligned to their highest version.
+    pub versions: &'a BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub conflicts: Vec<Conflict>,
+}
+
+/// A package that is referenced with more than one version.
+#[derive(Debug, Serialize)]
+pub struct Conflict {
+    pub package: String,
+    pub versions: Vec<VersionUsage>,
+    /// The executables that merge more than one version of the package into their output through their closure.
+    pub executables: Vec<PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VersionUsage {
+    pub version: String,
+    pub projects: Vec<PathBuf>,
+}
+
+pub fn run(options: Options) {
+    debug!("Finding package conflicts with options {:?}", &options);
+
+    let Options {
+        search_path,
+        format,
+        fix,
+        versions,
+    } = options;
+
+    let current_dir = std::env::current_dir().unwrap();
+    let Root {
+        root_dir,
+        search_path,
+    } = root_dir(search_path).unwrap();
+
+    let mut projects = find_projects(&root_dir);
+    projects.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let conflicts = package_conflicts(&projects, &root_dir, &search_path);
+
+    let report = Report {
+        conflicts: conflicts
+            .iter()
+            .map(|conflict| Conflict {
+                package: conflict.package.clone(),
+                versions: conflict
+                    .versions
+                    .iter()
+                    .map(|(version, indices)| VersionUsage {
+                        version: version.clone(),
+                        projects: indices
+                            .iter()
+                            .map(|&index| relative_path(&current_dir, &projects[index].path))
+                            .collect(),
+                    })
+                    .collect(),
+                executables: conflict
+                    .executables
+                    .iter()
+                    .map(|&index| relative_path(&current_dir, &projects[index].path))
+                    .collect(),
+            })
+            .collect(),
+    };
+
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(std::io::stdout(), &report).unwrap();
+            println!();
+        }
+        OutputFormat::Table => print_table(&report),
+    }
+
+    if !fix {
+        return;
+    }
+
+    for conflict in conflicts.iter() {
+        let target = match versions.get(&conflict.package.to_lowercase()) {
+            Some(version) => version.as_str(),
+            None => match conflict
+                .versions
+                .keys()
+                .filter(|version| is_plain_version(version))
+                .max_by(|a, b| compare_versions(a, b))
+            {
+                Some(version) => version.as_str(),
+                None => {
+                    warn!(
+                        "Not aligning {}, none of its versions is a plain version",
+                        conflict.package
+                    );
+                    continue;
+                }
+            },
+        };
+
+        for (version, indices) in conflict.versions.iter() {
+            if version == target {
+                continue;
+            }
+            if !is_plain_version(version) {
+                warn!(
+                    "Not updating {} {}, it is not a plain version",
+                    conflict.package, version
+                );
+                continue;
+            }
+            for &index in indices.iter() {
+                let path = relative_path(&current_dir, &projects[index].path);
+                if set_package_version(&projects[index].path, &conflict.package, version, target)
+                    .unwrap()
+                {
+                    eprintln!(
+                        "{}: updated {} from {} to {}",
+                        path.display(),
+                        conflict.package,
+                        version,
+                        target
+                    );
+                } else {
+                    warn!(
+                        "{}: left {} {} unchanged, the reference could not be found in the project file",
+                        path.display(),
+                        conflict.package,
+                        version
+                    );
+                }
+            }
+        }
+    }
+}
+
+struct PackageConflict {
+    package: String,
+    /// Maps each version to the indices of the projects that reference it.
+    versions: BTreeMap<String, BTreeSet<usize>>,
+    executables: Vec<usize>,
+}
+
+/// Finds the packages referenced with more than one version by the projects under the search path or by the
+/// projects merged into the output of an executable under the search path.
+fn package_conflicts(
+    projects: &[Project],
+    root_dir: &Path,
+    search_path: &Path,
+) -> Vec<PackageConflict> {
+    // Build only references do not copy the output of the referenced project.
+    let edges = project_edges(projects, root_dir)
+        .into_iter()
+        .filter(|&(from, to)| {
+            !projects[from]
+                .build_only_project_references
+                .contains(&projects[to].path)
+        })
+        .collect::<Vec<_>>();
+    let outgoing = crate::graph::outgoing_edges(projects.len(), &edges);
+
+    let mut included = projects
+        .iter()
+        .map(|project| project.path.starts_with(search_path))
+        .collect::<Vec<_>>();
+
+    let executables = (0..projects.len())
+        .filter(|&index| included[index] && projects[index].is_exe)
+        .map(|index| (index, crate::graph::reachable(&outgoing, index)))
+        .collect::<Vec<_>>();
+    for (_, closure) in executables.iter() {
+        for (included, &in_closure) in included.iter_mut().zip(closure.iter()) {
+            *included |= in_closure;
+        }
+    }
+
+    // NuGet package ids are case insensitive, the first spelling found is reported.
+    let mut packages = BTreeMap::<String, PackageConflict>::new();
+    for (index, project) in projects.iter().enumerate() {
+        if !included[index] {
+            continue;
+        }
+        for package in project.package_references.iter() {
+            packages
+                .entry(package.name.to_lowercase())
+                .or_insert_with(|| PackageConflict {
+                    package: package.name.clone(),
+                    versions: BTreeMap::new(),
+                    executables: Vec::new(),
+                })
+                .versions
+                .entry(package.version.clone())
+                .or_default()
+                .insert(index);
+        }
+    }
+
+    let mut conflicts = packages
+        .into_values()
+        .filter(|conflict| conflict.versions.len() > 1)
+        .collect::<Vec<_>>();
+
+    for conflict in conflicts.iter_mut() {
+        for (executable, closure) in executables.iter() {
+            let merged_versions = conflict
+                .versions
+                .values()
+                .filter(|indices| indices.iter().any(|&index| closure[index]))
+                .count();
+            if merged_versions > 1 {
+                conflict.executables.push(*executable);
+            }
+        }
+    }
+
+    conflicts
+}
+
+fn print_table(report: &Report) {
+    if report.conflicts.is_empty() {
+        println!("No package version conflicts found.");
+        return;
+    }
+
+    for conflict in report.conflicts.iter() {
+        println!(
+            "{} is referenced with {} versions:",
+            conflict.package,
+            conflict.versions.len()
+        );
+        for usage in conflict.versions.iter() {
+            println!("  {}", usage.version);
+            for path in usage.projects.iter() {
+                println!("    {}", path.display());
+            }
+        }
+        if !conflict.executables.is_empty() {
+            println!("  merged into the output of:");
+            for path in conflict.executables.iter() {
+                println!("    {}", path.display());
+            }
+        }
+    }
+}
+
+/// Updates the version of the matching package references, returns whether any reference was changed.
+fn set_package_version(
+    csproj_path: &Path,
+    package: &str,
+    from_version: &str,
+    to_version: &str,
+) -> Result<bool, Error> {
+    let mut changed = false;
+
+    transform_xml_file(csproj_path, |mut root| {
+        process_tree(&mut root, |element| {
+            if element.name != "PackageReference" {
+                return;
+            }
+            let matches = match element.attributes.get("Include") {
+                Some(include) => include.eq_ignore_ascii_case(package),
+                None => false,
+            };
+            if !matches {
+                return;
+            }
+            if element.attributes.get("Version").map(String::as_str) == Some(from_version) {
+                element
+                    .attributes
+                    .insert("Version".to_string(), to_version.to_string());
+                changed = true;
+            }
+            for child in child_elements_mut(element) {
+                if child.name == "Version"
+                    && child.get_text().as_deref().map(str::trim) == Some(from_version)
+                {
+                    child.children = vec![xmltree::XMLNode::Text(to_version.to_string())];
+                    changed = true;
+                }
+            }
+        });
+
+        if changed {
+            Some(root)
+        } else {
+            None
+        }
+    })?;
+
+    Ok(changed)
+}
+
+/// Version ranges and versions using MSBuild properties can not be compared.
+fn is_plain_version(version: &str) -> bool {
+    version.starts_with(|c: char| c.is_ascii_digit())
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'))
+}
+
+/// Compares NuGet versions: numeric release parts first, then a release is higher than any of its pre-releases.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn split(version: &str) -> (Vec<u64>, Option<&str>) {
+        let version = version.split('+').next().unwrap();
+        let (release, pre_release) = match version.find('-') {
+            Some(index) => (&version[..index], Some(&version[index + 1..])),
+            None => (version, None),
+        };
+        let mut parts = release
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect::<Vec<u64>>();
+        while parts.last() == Some(&0) {
+            parts.pop();
+        }
+        (parts, pre_release)
+    }
+
+    let (a_release, a_pre_release) = split(a);
+    let (b_release, b_pre_release) = split(b);
+
+    a_release
+        .cmp(&b_release)
+        .then_with(|| match (a_pre_release, b_pre_release) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (Some(a), Some(b)) => {
+                let identifier = |part: &str| match part.parse::<u64>() {
+                    // Numeric identifiers have lower precedence than alphanumeric ones.
+                    Ok(number) => (0, number, String::new()),
+                    Err(_) => (1, 0, part.to_lowercase()),
+                };
+                a.split('.')
+                    .map(identifier)
+                    .cmp(b.split('.').map(identifier))
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_versions_orders_releases_and_pre_releases() {
+        let mut versions = vec![
+            "13.0.1",
+            "2.0",
+            "13.0.1-beta.2",
+            "12.0.3",
+            "13.0.1-beta.10",
+            "13.0.1-alpha",
+            "2.0.0.0",
+        ];
+        versions.sort_by(|a, b| compare_versions(a, b));
+        assert_eq!(
+            versions,
+            vec![
+                "2.0",
+                "2.0.0.0",
+                "12.0.3",
+                "13.0.1-alpha",
+                "13.0.1-beta.2",
+                "13.0.1-beta.10",
+                "13.0.1",
+            ]
+        );
+        assert!(is_plain_version("13.0.1-beta.2"));
+        assert!(!is_plain_version("[12.0,14.0)"));
+        assert!(!is_plain_version("$(NewtonsoftVersion)"));
+    }
+}
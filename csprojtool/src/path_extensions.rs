@@ -68,6 +68,11 @@ fn join_components<'a, I: Iterator<Item = Component<'a>>>(result: &mut PathBuf,
     }
 }
 
+/// Solution and project files usually use backslashes, convert them so the path can be used on this platform.
+pub fn native_separators(path: &str) -> String {
+    path.replace('\\', std::path::MAIN_SEPARATOR_STR)
+}
+
 pub trait PathExt {
     fn simplify(&self) -> PathBuf;
 
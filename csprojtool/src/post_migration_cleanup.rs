@@ -18,7 +18,24 @@ pub fn post_migration_cleanup(options: &PostMigrationCleanupOptions) {
     } = *options;
 
     // TODO(mickvangelderen): This is inefficient, we're parsing the projects twice.
-    let projects = parse_projects(search_path, glob_matcher, follow_project_references);
+    let discovery = crate::discovery::discover(crate::discovery::Options {
+        search_path,
+        glob: Some(glob_matcher),
+        follow_incoming_project_references: false,
+        follow_outgoing_project_references: follow_project_references,
+    })
+    .unwrap_or_else(|e| {
+        panic!(
+            "Failed to find projects in {}: {}",
+            search_path.display(),
+            e
+        )
+    });
+    let projects = discovery
+        .projects
+        .into_iter()
+        .zip(discovery.included)
+        .filter_map(|(project, included)| if included { Some(project) } else { None });
 
     let cwd = std::fs::canonicalize(std::env::current_dir().unwrap()).unwrap();
 
@@ -0,0 +1,203 @@
+use log::{debug, warn};
+use xmltree::XMLNode;
+
+use crate::csproj::*;
+use crate::discovery::*;
+use crate::path_extensions::*;
+use crate::xml_extensions::*;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options<'a> {
+    pub search_path: &'a Path,
+    /// Only list the references that would be removed.
+    pub dry_run: bool,
+    /// References to projects matching these globs, relative to the root directory, are never removed.
+    pub keep: &'a globset::GlobSet,
+}
+
+pub fn prune_references(options: Options) {
+    debug!("Pruning project references with options {:?}", &options);
+
+    let Options {
+        search_path,
+        dry_run,
+        keep,
+    } = options;
+
+    let current_dir = std::env::current_dir().unwrap();
+    let Root {
+        root_dir,
+        search_path,
+    } = root_dir(search_path).unwrap();
+
+    let mut projects = find_projects(&root_dir);
+    projects.sort_by(|a, b| a.path.cmp(&b.path));
+
+    // Build only references do not make the referenced assembly available transitively.
+    let edges = project_edges(&projects, &root_dir)
+        .into_iter()
+        .filter(|&(from, to)| {
+            !projects[from]
+                .build_only_project_references
+                .contains(&projects[to].path)
+        })
+        .collect::<Vec<_>>();
+    let outgoing = crate::graph::outgoing_edges(projects.len(), &edges);
+
+    // References with private assets do not make the referenced project available to consumers.
+    let transitive_edges = edges
+        .iter()
+        .copied()
+        .filter(|&(from, to)| {
+            !projects[from]
+                .private_project_references
+                .contains(&projects[to].path)
+        })
+        .collect::<Vec<_>>();
+    let transitive = crate::graph::outgoing_edges(projects.len(), &transitive_edges);
+
+    for cycle in crate::graph::cycles(&outgoing) {
+        warn!(
+            "Not pruning the references of {} projects in a cycle starting at {}",
+            cycle.len(),
+            projects[cycle[0]].path.display()
+        );
+    }
+
+    let mut removals = BTreeMap::<usize, BTreeSet<PathBuf>>::new();
+
+    for (from, to, via) in crate::graph::redundant_edges(&outgoing, &transitive) {
+        let from_path = &projects[from].path;
+        let to_path = &projects[to].path;
+
+        if !from_path.starts_with(&search_path) {
+            continue;
+        }
+
+        // Legacy projects and projects that disable transitive project references only see their direct references.
+        if !projects[from].is_sdk || projects[from].disable_transitive_project_references {
+            debug!(
+                "Not pruning the references of {}, it does not get project references transitively",
+                from_path.display()
+            );
+            continue;
+        }
+
+        if keep.is_match(relative_path(&root_dir, to_path)) {
+            println!(
+                "{}: keeping {} (allowed explicitly)",
+                relative_path(&current_dir, from_path).display(),
+                relative_path(&current_dir, to_path).display(),
+            );
+            continue;
+        }
+
+        println!(
+            "{}: {} {} (referenced through {})",
+            relative_path(&current_dir, from_path).display(),
+            if dry_run { "would remove" } else { "removing" },
+            relative_path(&current_dir, to_path).display(),
+            relative_path(&current_dir, &projects[via].path).display(),
+        );
+        removals.entry(from).or_default().insert(to_path.clone());
+    }
+
+    if removals.is_empty() {
+        println!("No project references to remove.");
+        return;
+    }
+
+    if dry_run {
+        return;
+    }
+
+    for (from, to_paths) in removals.iter() {
+        remove_project_references(&projects[*from].path, to_paths).unwrap();
+    }
+}
+
+fn remove_project_references(
+    csproj_path: &Path,
+    to_paths: &BTreeSet<PathBuf>,
+) -> Result<(), Error> {
+    let csproj_dir = csproj_path.parent().unwrap();
+
+    let is_removed = |element: &xmltree::Element| {
+        if element.name != "ProjectReference" {
+            return false;
+        }
+        let ref_path = match element.attributes.get("Include") {
+            Some(include) => csproj_dir.join(native_separators(include)).simplify(),
+            None => return false,
+        };
+        match std::fs::canonicalize(&ref_path) {
+            Ok(ref_path) => to_paths.contains(&ref_path),
+            Err(_) => false,
+        }
+    };
+
+    transform_xml_file(csproj_path, |mut root| {
+        remove_elements(&mut root, &is_removed);
+        Some(root)
+    })
+}
+
+/// Removes the matching descendants and the item groups that only contained removed elements. Returns whether
+/// anything was removed.
+fn remove_elements<F>(element: &mut xmltree::Element, is_removed: &F) -> bool
+where
+    F: Fn(&xmltree::Element) -> bool,
+{
+    let mut removed_any = false;
+    element.children.retain_mut(|child| match child {
+        XMLNode::Element(child) => {
+            if is_removed(child) {
+                removed_any = true;
+                return false;
+            }
+            let removed = remove_elements(child, is_removed);
+            removed_any |= removed;
+            !(removed && child.name == "ItemGroup" && all_children_whitespace(child))
+        }
+        _ => true,
+    });
+    removed_any
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remove_elements_only_drops_item_groups_it_emptied() {
+        let mut root = xmltree::Element::parse(
+            r#"<Project>
+  <ItemGroup>
+  </ItemGroup>
+  <ItemGroup>
+    <ProjectReference Include="A.csproj" />
+  </ItemGroup>
+  <ItemGroup>
+    <ProjectReference Include="A.csproj" />
+    <ProjectReference Include="B.csproj" />
+  </ItemGroup>
+</Project>"#
+                .as_bytes(),
+        )
+        .unwrap();
+
+        let removed = remove_elements(&mut root, &|element: &xmltree::Element| {
+            element.attributes.get("Include").map(String::as_str) == Some("A.csproj")
+        });
+
+        assert!(removed);
+        let item_groups = child_elements(&root).collect::<Vec<_>>();
+        assert_eq!(item_groups.len(), 2);
+        assert!(all_children_whitespace(item_groups[0]));
+        assert_eq!(child_elements(item_groups[1]).count(), 1);
+    }
+}
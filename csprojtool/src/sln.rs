@@ -1,12 +1,19 @@
 mod file;
+pub mod parse;
 mod types;
 
+pub use file::guid_from_path;
+
 use log::debug;
 use log::warn;
 
 use crate::csproj::*;
 use crate::path_extensions::*;
+use crate::utils::entry_is_sln;
+use std::collections::BTreeMap;
 use std::path::Path;
+use std::path::PathBuf;
+use uuid::Uuid;
 
 #[derive(Debug, Copy, Clone)]
 pub struct Options<'a> {
@@ -14,8 +21,26 @@ pub struct Options<'a> {
     pub search_path: &'a Path,
     pub follow_incoming_project_references: bool,
     pub follow_outgoing_project_references: bool,
+    /// Reuse the guids of folders and projects found in an existing solution file.
+    pub keep_guids: bool,
+    /// Path to a json file with additional build dependencies.
+    pub build_dependencies_path: Option<&'a Path>,
+    /// Overrides the VisualStudioVersion, otherwise it is taken from the existing solution file.
+    pub visual_studio_version: Option<&'a str>,
 }
 
+#[derive(Debug, Copy, Clone)]
+pub struct WriteOptions<'a> {
+    /// Reuse the guids of folders, projects and the solution found in an existing solution file.
+    pub keep_guids: bool,
+    pub build_dependencies: &'a BuildDependencies,
+    /// Overrides the VisualStudioVersion, otherwise it is taken from the existing solution file.
+    pub visual_studio_version: Option<&'a str>,
+}
+
+/// Maps a project path to the paths of the projects that have to be built before it.
+pub type BuildDependencies = BTreeMap<PathBuf, Vec<PathBuf>>;
+
 pub fn sln(options: Options) {
     debug!("Generating solution with options {:?}", &options);
 
@@ -24,6 +49,9 @@ pub fn sln(options: Options) {
         search_path,
         follow_incoming_project_references,
         follow_outgoing_project_references,
+        keep_guids,
+        build_dependencies_path,
+        visual_studio_version,
     } = options;
 
     let projects = crate::list::list(crate::list::Options {
@@ -32,20 +60,183 @@ pub fn sln(options: Options) {
         follow_outgoing_project_references,
     });
 
-    let sln = create_solution(&sln_path, projects.into_iter());
+    let build_dependencies = build_dependencies_path
+        .map(read_build_dependencies)
+        .unwrap_or_default();
+
+    write_solution(
+        sln_path,
+        projects.into_iter(),
+        WriteOptions {
+            keep_guids,
+            build_dependencies: &build_dependencies,
+            visual_studio_version,
+        },
+    );
+}
+
+/// Reads a json object that maps project paths to arrays of project paths, relative to the file.
+pub fn read_build_dependencies(path: &Path) -> BuildDependencies {
+    let file = std::fs::File::open(path).unwrap_or_else(|e| {
+        panic!(
+            "Failed to open build dependencies {}: {}",
+            path.display(),
+            e
+        )
+    });
+    let raw: BTreeMap<String, Vec<String>> = serde_json::from_reader(std::io::BufReader::new(file))
+        .unwrap_or_else(|e| {
+            panic!(
+                "Failed to parse build dependencies {}: {}",
+                path.display(),
+                e
+            )
+        });
+
+    let dir = path.simplified_absolute().unwrap();
+    let dir = dir.parent().unwrap();
+    let resolve = |rel_path: &str| {
+        let abs_path = dir.join(rel_path).simplify();
+        std::fs::canonicalize(&abs_path).unwrap_or_else(|e| {
+            panic!(
+                "Failed to resolve {} in build dependencies {}: {}",
+                abs_path.display(),
+                path.display(),
+                e
+            )
+        })
+    };
+
+    raw.iter()
+        .map(|(project, dependencies)| {
+            (
+                resolve(project),
+                dependencies
+                    .iter()
+                    .map(|dependency| resolve(dependency))
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+/// Finds all solution files under the root directory, respecting ignore files.
+pub fn find_solutions(root_dir: &Path) -> Vec<PathBuf> {
+    ignore::WalkBuilder::new(root_dir)
+        .build()
+        .filter_map(|entry| match entry {
+            Ok(entry) => {
+                if entry_is_sln(&entry) {
+                    Some(std::fs::canonicalize(entry.path()).unwrap())
+                } else {
+                    None
+                }
+            }
+            Err(e) => {
+                warn!("Skipping entry while searching for solutions: {}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+pub fn write_solution(
+    sln_path: &Path,
+    projects: impl Iterator<Item = Project>,
+    options: WriteOptions,
+) {
+    let WriteOptions {
+        keep_guids,
+        build_dependencies,
+        visual_studio_version,
+    } = options;
+
+    let existing = read_existing_solution(sln_path);
+
+    let known_guids = match (&existing, keep_guids) {
+        (Some(existing), true) => existing.known_guids(),
+        _ => file::KnownGuids::default(),
+    };
+
+    let header = {
+        let default = file::Header::default();
+        let existing = existing.as_ref();
+        file::Header {
+            visual_studio_version: visual_studio_version
+                .map(str::to_owned)
+                .or_else(|| existing.and_then(|e| e.visual_studio_version.clone()))
+                .unwrap_or(default.visual_studio_version),
+            minimum_visual_studio_version: existing
+                .and_then(|e| e.minimum_visual_studio_version.clone())
+                .unwrap_or(default.minimum_visual_studio_version),
+        }
+    };
+
+    let solution_guid = existing
+        .as_ref()
+        .and_then(|existing| existing.solution_guid)
+        .filter(|_| keep_guids)
+        .unwrap_or_else(|| guid_from_path(sln_path.file_name().unwrap().to_str().unwrap()));
+
+    let sln = create_solution(
+        sln_path,
+        projects,
+        header,
+        solution_guid,
+        &known_guids,
+        build_dependencies,
+    );
 
     let file = std::fs::File::create(&sln_path).unwrap();
     let mut writer = std::io::BufWriter::new(file);
     sln.write(&mut writer).unwrap();
 }
 
-fn create_solution(sln_path: &Path, projects: impl Iterator<Item = Project>) -> file::SolutionFile {
+fn read_existing_solution(sln_path: &Path) -> Option<parse::ParsedSolution> {
+    match parse::ParsedSolution::read(sln_path) {
+        Ok(existing) => Some(existing),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => panic!(
+            "Failed to read existing solution {}: {}",
+            sln_path.display(),
+            e
+        ),
+    }
+}
+
+fn create_solution(
+    sln_path: &Path,
+    projects: impl Iterator<Item = Project>,
+    header: file::Header,
+    solution_guid: Uuid,
+    known_guids: &file::KnownGuids,
+    build_dependencies: &BuildDependencies,
+) -> file::SolutionFile {
     let mut root = file::Directory::default();
     let sln_path = sln_path.simplified_absolute().unwrap().simplify();
     let sln_dir = sln_path.parent().unwrap();
     debug!("Solution dir {}", sln_dir.display());
 
-    for project in projects {
+    let projects = projects
+        .map(|project| {
+            let sln_relative_path = relative_path(sln_dir, &project.path)
+                .components()
+                .map(|comp| comp.as_os_str().to_str().unwrap())
+                .collect::<Vec<_>>()
+                .join("\\");
+            let guid = project
+                .project_guid
+                .unwrap_or_else(|| file::known_guid_or_from_path(known_guids, &sln_relative_path));
+            (project, sln_relative_path, guid)
+        })
+        .collect::<Vec<_>>();
+
+    let path_to_guid = projects
+        .iter()
+        .map(|(project, _, guid)| (project.path.clone(), *guid))
+        .collect::<BTreeMap<_, _>>();
+
+    for (project, sln_relative_path, guid) in projects.iter() {
         let rel_project_path = relative_path(sln_dir, &project.path);
 
         debug!(
@@ -54,18 +245,47 @@ fn create_solution(sln_path: &Path, projects: impl Iterator<Item = Project>) ->
             rel_project_path.display()
         );
 
-        let mut components = rel_project_path.components().peekable();
+        let mut dependencies = project
+            .build_only_project_references
+            .iter()
+            .chain(build_dependencies.get(&project.path).into_iter().flatten())
+            .filter_map(|path| {
+                let guid = path_to_guid.get(path).copied();
+                if guid.is_none() {
+                    warn!(
+                        "Build dependency {} of {} is not part of the solution",
+                        path.display(),
+                        project.path.display()
+                    );
+                }
+                guid
+            })
+            .collect::<Vec<_>>();
+        dependencies.sort();
+        dependencies.dedup();
+
+        // Projects outside of the solution directory are placed in a folder per number of leading parent directory
+        // components, like `(up 2)` for `../../a/A.csproj`, so they do not collide with the projects inside it.
+        let up = rel_project_path
+            .components()
+            .take_while(|comp| matches!(comp, std::path::Component::ParentDir))
+            .count();
+        let mut components = (up > 0)
+            .then(|| format!("(up {})", up))
+            .into_iter()
+            .chain(
+                rel_project_path
+                    .components()
+                    .skip(up)
+                    .map(|comp| match comp {
+                        std::path::Component::Normal(val) => val.to_str().unwrap().to_owned(),
+                        _ => panic!("Unexpected path component!"),
+                    }),
+            )
+            .peekable();
 
         let mut dir = &mut root;
         while let Some(comp) = components.next() {
-            let comp = match comp {
-                std::path::Component::ParentDir => {
-                    panic!("Can not reference projects outside of solution directory!")
-                }
-                std::path::Component::Normal(val) => val.to_str().unwrap().to_owned(),
-                _ => panic!("Unexpected path component!"),
-            };
-
             if components.peek().is_some() {
                 dir = match dir
                     .nodes
@@ -76,15 +296,24 @@ fn create_solution(sln_path: &Path, projects: impl Iterator<Item = Project>) ->
                     file::Node::Project(_) => panic!("Project path used as directory!"),
                 };
             } else {
-                dir.nodes.insert(
+                let previous = dir.nodes.insert(
                     comp,
                     file::Node::Project(file::Project {
-                        guid: project.project_guid,
+                        path: sln_relative_path.clone(),
+                        guid: *guid,
+                        is_sdk: project.is_sdk,
+                        dependencies: dependencies.clone(),
                     }),
                 );
+                if previous.is_some() {
+                    panic!(
+                        "Solution path of {} is already used by another project or folder!",
+                        project.path.display()
+                    );
+                }
             }
         }
     }
 
-    file::SolutionFile::new(root)
+    file::SolutionFile::new(header, solution_guid, root, known_guids)
 }
@@ -1,19 +1,27 @@
 use crate::sln::types::*;
-use siphasher::sip128::Hasher128;
 use std::collections::BTreeMap;
-use std::hash::Hash;
 use std::io::Write;
 use uuid::Uuid;
 
-const HEADER: &'static str = r###"
-Microsoft Visual Studio Solution File, Format Version 12.00
-# Visual Studio Version 16
-VisualStudioVersion = 16.0.30114.105
-MinimumVisualStudioVersion = 10.0.40219.1
-"###;
+const BOM: &'static str = "\u{FEFF}";
+const DEFAULT_VISUAL_STUDIO_VERSION: &'static str = "16.0.30114.105";
+const DEFAULT_MINIMUM_VISUAL_STUDIO_VERSION: &'static str = "10.0.40219.1";
 
-const FOLDER_UUID: Uuid = Uuid::from_bytes(0x2150E3338FDC42A394741A3956D46DE8u128.to_be_bytes());
-const PROJECT_UUID: Uuid = Uuid::from_bytes(0xFAE04EC0301F11D3BF4B00C04F79EFBCu128.to_be_bytes());
+pub const FOLDER_UUID: Uuid =
+    Uuid::from_bytes(0x2150E3338FDC42A394741A3956D46DE8u128.to_be_bytes());
+/// Project type of legacy C# projects.
+const LEGACY_PROJECT_UUID: Uuid =
+    Uuid::from_bytes(0xFAE04EC0301F11D3BF4B00C04F79EFBCu128.to_be_bytes());
+/// Project type Visual Studio writes for SDK-style C# projects.
+const SDK_PROJECT_UUID: Uuid =
+    Uuid::from_bytes(0x9A19103F16F74668BE549A1E7A4F7556u128.to_be_bytes());
+
+/// Namespace for the name-based guids of solution folders and projects without a ProjectGuid. Changing
+/// this changes the guids of every generated solution.
+const GUID_NAMESPACE: Uuid = Uuid::from_bytes(0x6BA7B8148F4A4C6E9C2D5E0F3A1B7D42u128.to_be_bytes());
+
+/// Guids to reuse instead of generating them, keyed by the normalised path of the folder or project.
+pub type KnownGuids = BTreeMap<String, Uuid>;
 
 #[derive(Debug, Clone)]
 pub enum Node {
@@ -21,29 +29,90 @@ pub enum Node {
     Directory(Directory),
 }
 
+#[derive(Debug, Clone)]
+pub struct Header {
+    pub visual_studio_version: String,
+    pub minimum_visual_studio_version: String,
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Self {
+            visual_studio_version: DEFAULT_VISUAL_STUDIO_VERSION.to_owned(),
+            minimum_visual_studio_version: DEFAULT_MINIMUM_VISUAL_STUDIO_VERSION.to_owned(),
+        }
+    }
+}
+
+impl Header {
+    fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let major = self
+            .visual_studio_version
+            .split('.')
+            .next()
+            .unwrap_or_default();
+
+        // Visual Studio starts the file with an empty line and only includes "Version" since 2019.
+        writeln!(writer)?;
+        writeln!(
+            writer,
+            "Microsoft Visual Studio Solution File, Format Version 12.00"
+        )?;
+        match major.parse::<u32>() {
+            Ok(major) if major < 16 => writeln!(writer, "# Visual Studio {}", major)?,
+            _ => writeln!(writer, "# Visual Studio Version {}", major)?,
+        }
+        writeln!(
+            writer,
+            "VisualStudioVersion = {}",
+            self.visual_studio_version
+        )?;
+        writeln!(
+            writer,
+            "MinimumVisualStudioVersion = {}",
+            self.minimum_visual_studio_version
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SolutionFile {
+    pub header: Header,
+    pub solution_guid: Uuid,
     pub root: InnerRootDirectory,
 }
 
 impl SolutionFile {
-    pub fn new(root: Directory) -> Self {
+    pub fn new(
+        header: Header,
+        solution_guid: Uuid,
+        root: Directory,
+        known_guids: &KnownGuids,
+    ) -> Self {
         Self {
-            root: InnerRootDirectory::new(root),
+            header,
+            solution_guid,
+            root: InnerRootDirectory::new(root, known_guids),
         }
     }
 
+    /// Writes the solution the way Visual Studio does: with a UTF-8 BOM, CRLF line endings and the
+    /// global sections in the same order.
     pub fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        writer.write_all(HEADER.as_bytes())?;
+        let writer = &mut CrlfWriter(writer);
+
+        writer.write_all(BOM.as_bytes())?;
+        self.header.write(writer)?;
 
         self.root.write_projects(writer)?;
 
-        write!(writer, "Global\n")?;
+        writeln!(writer, "Global")?;
         self.write_global_section_solution_configuration_platforms(writer)?;
-        self.write_global_section_solution_properties(writer)?;
         self.root.write_project_configurations(writer)?;
+        self.write_global_section_solution_properties(writer)?;
         self.root.write_nested_projects(writer)?;
-        write!(writer, "EndGlobal\n")?;
+        self.write_global_section_extensibility_globals(writer)?;
+        writeln!(writer, "EndGlobal")?;
 
         Ok(())
     }
@@ -52,18 +121,18 @@ impl SolutionFile {
         &self,
         writer: &mut W,
     ) -> std::io::Result<()> {
-        write!(
+        writeln!(
             writer,
-            "\tGlobalSection(SolutionConfigurationPlatforms) = preSolution\n"
+            "\tGlobalSection(SolutionConfigurationPlatforms) = preSolution"
         )?;
 
         for conf in CONFIGURATIONS {
             for arch in PROCESSOR_ARCHITECTURES {
-                write!(writer, "\t\t{0}|{1} = {0}|{1}\n", conf, arch)?;
+                writeln!(writer, "\t\t{0}|{1} = {0}|{1}", conf, arch)?;
             }
         }
 
-        write!(writer, "\tEndGlobalSection\n")?;
+        writeln!(writer, "\tEndGlobalSection")?;
 
         Ok(())
     }
@@ -72,19 +141,54 @@ impl SolutionFile {
         &self,
         writer: &mut W,
     ) -> std::io::Result<()> {
-        write!(
+        writeln!(writer, "\tGlobalSection(SolutionProperties) = preSolution")?;
+        writeln!(writer, "\t\tHideSolutionNode = FALSE")?;
+        writeln!(writer, "\tEndGlobalSection")?;
+        Ok(())
+    }
+
+    fn write_global_section_extensibility_globals<W: Write>(
+        &self,
+        writer: &mut W,
+    ) -> std::io::Result<()> {
+        writeln!(
             writer,
-            "\tGlobalSection(SolutionProperties) = preSolution\n"
+            "\tGlobalSection(ExtensibilityGlobals) = postSolution"
         )?;
-        write!(writer, "\t\tHideSolutionNode = FALSE\n")?;
-        write!(writer, "\tEndGlobalSection\n")?;
+        writeln!(writer, "\t\tSolutionGuid = {{{:X}}}", self.solution_guid)?;
+        writeln!(writer, "\tEndGlobalSection")?;
         Ok(())
     }
 }
 
+/// Translates every `\n` into `\r\n`.
+struct CrlfWriter<W>(W);
+
+impl<W: Write> Write for CrlfWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        for (index, line) in buf.split(|&byte| byte == b'\n').enumerate() {
+            if index > 0 {
+                self.0.write_all(b"\r\n")?;
+            }
+            self.0.write_all(line)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Project {
-    pub guid: Option<Uuid>,
+    /// The path of the project relative to the solution directory, separated by backslashes.
+    pub path: String,
+    pub guid: Uuid,
+    /// Whether the project is SDK-style, which decides its project type guid.
+    pub is_sdk: bool,
+    /// Guids of projects that have to be built before this one.
+    pub dependencies: Vec<Uuid>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -99,10 +203,12 @@ pub enum InnerNode {
 }
 
 impl InnerNode {
-    pub fn new(path: &str, name: String, node: Node) -> Self {
+    pub fn new(path: &str, name: String, node: Node, known_guids: &KnownGuids) -> Self {
         match node {
-            Node::Directory(dir) => Self::Directory(InnerDirectory::new(path, name, dir)),
-            Node::Project(proj) => Self::Project(InnerProject::new(path, name, proj)),
+            Node::Directory(dir) => {
+                Self::Directory(InnerDirectory::new(path, name, dir, known_guids))
+            }
+            Node::Project(proj) => Self::Project(InnerProject::new(name, proj)),
         }
     }
 }
@@ -113,11 +219,11 @@ pub struct InnerRootDirectory {
 }
 
 impl InnerRootDirectory {
-    pub fn new(root: Directory) -> Self {
+    pub fn new(root: Directory, known_guids: &KnownGuids) -> Self {
         let nodes = root
             .nodes
             .into_iter()
-            .map(|(name, node)| InnerNode::new("", name, node))
+            .map(|(name, node)| InnerNode::new("", name, node, known_guids))
             .collect();
 
         Self { nodes }
@@ -135,9 +241,9 @@ impl InnerRootDirectory {
     }
 
     fn write_project_configurations<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        write!(
+        writeln!(
             writer,
-            "\tGlobalSection(ProjectConfigurationPlatforms) = postSolution\n"
+            "\tGlobalSection(ProjectConfigurationPlatforms) = postSolution"
         )?;
 
         for node in self.nodes.iter() {
@@ -147,23 +253,37 @@ impl InnerRootDirectory {
             }
         }
 
-        write!(writer, "\tEndGlobalSection\n")?;
+        writeln!(writer, "\tEndGlobalSection")?;
         Ok(())
     }
 
     fn write_nested_projects<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        write!(writer, "\tGlobalSection(NestedProjects) = preSolution\n")?;
+        let has_folders = self.nodes.iter().any(|node| match node {
+            InnerNode::Directory(dir) => !dir.is_skipped(),
+            InnerNode::Project(_) => false,
+        });
+
+        // Visual Studio omits the section when there is nothing to nest.
+        if !has_folders {
+            return Ok(());
+        }
+
+        writeln!(writer, "\tGlobalSection(NestedProjects) = preSolution")?;
 
         for node in self.nodes.iter() {
             match node {
-                InnerNode::Directory(dir) => dir.write_nested_projects(writer)?,
+                InnerNode::Directory(dir) => {
+                    if !dir.is_skipped() {
+                        dir.write_nested_projects(writer)?
+                    }
+                }
                 InnerNode::Project(_) => {
                     // Projects are implicitly placed under the root if the nested project is omitted.
                 }
             }
         }
 
-        write!(writer, "\tEndGlobalSection\n")?;
+        writeln!(writer, "\tEndGlobalSection")?;
         Ok(())
     }
 }
@@ -177,13 +297,13 @@ pub struct InnerDirectory {
 }
 
 impl InnerDirectory {
-    pub fn new(path: &str, name: String, dir: Directory) -> Self {
+    pub fn new(path: &str, name: String, dir: Directory, known_guids: &KnownGuids) -> Self {
         let path = join_str_path(path, &name);
-        let guid = guid_from_hash(&path);
+        let guid = known_guid_or_from_path(known_guids, &path);
         let nodes = dir
             .nodes
             .into_iter()
-            .map(|(name, node)| InnerNode::new(&path, name, node))
+            .map(|(name, node)| InnerNode::new(&path, name, node, known_guids))
             .collect();
         Self {
             path,
@@ -205,18 +325,18 @@ impl InnerDirectory {
         }
     }
 
+    /// Directories that only contain a project with the same name are not written.
+    fn is_skipped(&self) -> bool {
+        matches!(self.contains_single_project(), Some(proj) if proj.name == self.name)
+    }
+
     fn write_projects<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        match self.contains_single_project() {
-            Some(proj) if proj.name == self.name => {
-                // Skip writing this directory.
-            }
-            _ => {
-                write!(
-                    writer,
-                    "Project(\"{{{0:X}}}\") = \"{1}\", \"{1}\", \"{{{2:X}}}\"\nEndProject\n",
-                    FOLDER_UUID, &self.name, self.guid
-                )?;
-            }
+        if !self.is_skipped() {
+            writeln!(
+                writer,
+                "Project(\"{{{0:X}}}\") = \"{1}\", \"{1}\", \"{{{2:X}}}\"\nEndProject",
+                FOLDER_UUID, &self.name, self.guid
+            )?;
         }
 
         for node in self.nodes.iter() {
@@ -246,14 +366,14 @@ impl InnerDirectory {
             parent: Uuid,
             child: Uuid,
         ) -> std::io::Result<()> {
-            write!(writer, "\t\t{{{0:X}}} = {{{1:X}}}\n", child, parent)
+            writeln!(writer, "\t\t{{{0:X}}} = {{{1:X}}}", child, parent)
         }
 
         for node in self.nodes.iter() {
             match node {
                 InnerNode::Directory(dir) => {
                     match dir.contains_single_project() {
-                        Some(proj) if proj.name == dir.name => {
+                        Some(proj) if dir.is_skipped() => {
                             // This directory is skipped so we will write a link from the parent dir to the only project directly here.
                             write_nested_project(writer, self.guid, proj.guid)?;
                         }
@@ -275,32 +395,64 @@ impl InnerDirectory {
 pub struct InnerProject {
     pub path: String,
     pub guid: Uuid,
+    pub is_sdk: bool,
     pub name: String,
+    pub dependencies: Vec<Uuid>,
 }
 
 impl InnerProject {
-    pub fn new(path: &str, name: String, proj: Project) -> Self {
-        let path = join_str_path(path, &name);
+    pub fn new(name: String, proj: Project) -> Self {
+        let Project {
+            path,
+            guid,
+            is_sdk,
+            dependencies,
+        } = proj;
         let name = name.strip_suffix(".csproj").unwrap().to_owned();
-        let guid = proj.guid.unwrap_or_else(|| guid_from_hash(&path));
-        Self { path, name, guid }
+        Self {
+            path,
+            name,
+            guid,
+            is_sdk,
+            dependencies,
+        }
     }
 
     fn write_project<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        write!(
+        writeln!(
             writer,
-            "Project(\"{{{0:X}}}\") = \"{1}\", \"{2}\", \"{{{3:X}}}\"\nEndProject\n",
-            PROJECT_UUID, self.name, self.path, self.guid
-        )
+            "Project(\"{{{0:X}}}\") = \"{1}\", \"{2}\", \"{{{3:X}}}\"",
+            if self.is_sdk {
+                SDK_PROJECT_UUID
+            } else {
+                LEGACY_PROJECT_UUID
+            },
+            self.name,
+            self.path,
+            self.guid
+        )?;
+
+        if !self.dependencies.is_empty() {
+            writeln!(
+                writer,
+                "\tProjectSection(ProjectDependencies) = postProject"
+            )?;
+            for dependency in self.dependencies.iter() {
+                writeln!(writer, "\t\t{{{0:X}}} = {{{0:X}}}", dependency)?;
+            }
+            writeln!(writer, "\tEndProjectSection")?;
+        }
+
+        writeln!(writer, "EndProject")
     }
 
     pub fn write_project_configuration<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
         for conf in CONFIGURATIONS {
             for arch in PROCESSOR_ARCHITECTURES {
                 for suffix in ["ActiveCfg", "Build.0"] {
-                    write!(
+                    writeln!(
                         writer,
-                        "\t\t{{{guid:X}}}.{conf}|{arch}.{suffix} = {conf}|Any CPU\n",
+                        "\t\t{{{guid:X}}}.{conf}|{arch}.{suffix} = {conf}|Any CPU",
                         guid = self.guid,
                         conf = conf,
                         arch = arch,
@@ -314,10 +466,22 @@ impl InnerProject {
     }
 }
 
-pub fn guid_from_hash<H: Hash>(value: H) -> Uuid {
-    let mut hasher = siphasher::sip128::SipHasher::new();
-    value.hash(&mut hasher);
-    Uuid::from_bytes(hasher.finish128().as_bytes())
+/// Normalises a solution relative path so that equivalent paths map to the same guid.
+pub fn normalise_path(path: &str) -> String {
+    path.replace('/', "\\").to_lowercase()
+}
+
+/// Computes a stable name-based (version 5) guid from a solution relative path.
+pub fn guid_from_path(path: &str) -> Uuid {
+    Uuid::new_v5(&GUID_NAMESPACE, normalise_path(path).as_bytes())
+}
+
+/// Looks up the guid of a folder or project in the known guids, generating one from the path if absent.
+pub fn known_guid_or_from_path(known_guids: &KnownGuids, path: &str) -> Uuid {
+    known_guids
+        .get(&normalise_path(path))
+        .copied()
+        .unwrap_or_else(|| guid_from_path(path))
 }
 
 pub fn join_str_path(a: &str, b: &str) -> String {
@@ -327,3 +491,76 @@ pub fn join_str_path(a: &str, b: &str) -> String {
         [a, "\\", b].iter().copied().collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guid_from_path_is_stable_and_valid() {
+        let guid = guid_from_path("src\\App\\App.csproj");
+        assert_eq!(guid.get_version_num(), 5);
+        assert_eq!(guid.get_variant(), Some(uuid::Variant::RFC4122));
+        assert_eq!(guid, guid_from_path("SRC/App/app.csproj"));
+        assert_eq!(
+            guid,
+            Uuid::parse_str("b7ac4eb2-65ec-5bd9-a14a-bb105693e99c").unwrap()
+        );
+    }
+
+    #[test]
+    fn write_uses_bom_and_crlf() {
+        let header = Header {
+            visual_studio_version: "17.0.31903.59".to_owned(),
+            minimum_visual_studio_version: "10.0.40219.1".to_owned(),
+        };
+        let sln = SolutionFile::new(
+            header,
+            guid_from_path("All.sln"),
+            Directory::default(),
+            &KnownGuids::default(),
+        );
+
+        let mut bytes = Vec::new();
+        sln.write(&mut bytes).unwrap();
+        let text = String::from_utf8(bytes).unwrap();
+
+        assert!(text.starts_with("\u{FEFF}\r\nMicrosoft Visual Studio Solution File, Format Version 12.00\r\n# Visual Studio Version 17\r\n"));
+        assert!(!text.replace("\r\n", "").contains('\n'));
+        assert!(!text.contains("NestedProjects"));
+        assert!(text.ends_with("\tEndGlobalSection\r\nEndGlobal\r\n"));
+    }
+
+    #[test]
+    fn write_uses_project_type_by_sdk() {
+        let mut root = Directory::default();
+        for (name, is_sdk) in [("App.csproj", true), ("Legacy.csproj", false)] {
+            root.nodes.insert(
+                name.to_owned(),
+                Node::Project(Project {
+                    path: name.to_owned(),
+                    guid: guid_from_path(name),
+                    is_sdk,
+                    dependencies: Vec::new(),
+                }),
+            );
+        }
+        let sln = SolutionFile::new(
+            Header::default(),
+            guid_from_path("All.sln"),
+            root,
+            &KnownGuids::default(),
+        );
+
+        let mut bytes = Vec::new();
+        sln.write(&mut bytes).unwrap();
+        let text = String::from_utf8(bytes).unwrap();
+
+        assert!(text.contains(
+            "Project(\"{9A19103F-16F7-4668-BE54-9A1E7A4F7556}\") = \"App\", \"App.csproj\""
+        ));
+        assert!(text.contains(
+            "Project(\"{FAE04EC0-301F-11D3-BF4B-00C04F79EFBC}\") = \"Legacy\", \"Legacy.csproj\""
+        ));
+    }
+}
@@ -0,0 +1,220 @@
+use crate::path_extensions::*;
+use crate::sln::file::{join_str_path, normalise_path, KnownGuids, FOLDER_UUID};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct ParsedSolution {
+    pub path: PathBuf,
+    pub entries: Vec<Entry>,
+    /// Maps the guid of a nested entry to the guid of its parent folder.
+    pub nested_projects: BTreeMap<Uuid, Uuid>,
+    pub visual_studio_version: Option<String>,
+    pub minimum_visual_studio_version: Option<String>,
+    pub solution_guid: Option<Uuid>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub type_guid: Uuid,
+    pub name: String,
+    pub path: String,
+    pub guid: Uuid,
+}
+
+impl Entry {
+    pub fn is_folder(&self) -> bool {
+        self.type_guid == FOLDER_UUID
+    }
+}
+
+impl ParsedSolution {
+    pub fn read(path: &Path) -> std::io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(Self::parse(path.to_owned(), &contents))
+    }
+
+    pub fn parse(path: PathBuf, contents: &str) -> Self {
+        lazy_static! {
+            static ref PROJECT_REGEX: Regex = Regex::new(
+                r#"^Project\("\{([0-9a-fA-F\-]+)\}"\)\s*=\s*"([^"]*)"\s*,\s*"([^"]*)"\s*,\s*"\{([0-9a-fA-F\-]+)\}""#
+            )
+            .unwrap();
+            static ref NESTED_REGEX: Regex =
+                Regex::new(r#"^\{([0-9a-fA-F\-]+)\}\s*=\s*\{([0-9a-fA-F\-]+)\}$"#).unwrap();
+        }
+
+        let mut entries = Vec::new();
+        let mut nested_projects = BTreeMap::new();
+        let mut in_nested_projects = false;
+        let mut visual_studio_version = None;
+        let mut minimum_visual_studio_version = None;
+        let mut solution_guid = None;
+
+        for line in contents.trim_start_matches('\u{FEFF}').lines() {
+            let line = line.trim();
+            if let Some(captures) = PROJECT_REGEX.captures(line) {
+                match (Uuid::parse_str(&captures[1]), Uuid::parse_str(&captures[4])) {
+                    (Ok(type_guid), Ok(guid)) => entries.push(Entry {
+                        type_guid,
+                        name: captures[2].to_owned(),
+                        path: captures[3].to_owned(),
+                        guid,
+                    }),
+                    _ => log::warn!("Ignoring malformed solution entry: {}", line),
+                }
+            } else if let Some(value) = line.strip_prefix("VisualStudioVersion") {
+                visual_studio_version = parse_value(value).map(str::to_owned);
+            } else if let Some(value) = line.strip_prefix("MinimumVisualStudioVersion") {
+                minimum_visual_studio_version = parse_value(value).map(str::to_owned);
+            } else if let Some(value) = line.strip_prefix("SolutionGuid") {
+                solution_guid = parse_value(value).and_then(|value| {
+                    Uuid::parse_str(value.trim_matches(|c| c == '{' || c == '}')).ok()
+                });
+            } else if line.starts_with("GlobalSection(NestedProjects)") {
+                in_nested_projects = true;
+            } else if line == "EndGlobalSection" {
+                in_nested_projects = false;
+            } else if in_nested_projects {
+                if let Some(captures) = NESTED_REGEX.captures(line) {
+                    if let (Ok(child), Ok(parent)) =
+                        (Uuid::parse_str(&captures[1]), Uuid::parse_str(&captures[2]))
+                    {
+                        nested_projects.insert(child, parent);
+                    }
+                }
+            }
+        }
+
+        Self {
+            path,
+            entries,
+            nested_projects,
+            visual_studio_version,
+            minimum_visual_studio_version,
+            solution_guid,
+        }
+    }
+
+    /// Collects the guids of the folders, keyed by the path formed by the names of their ancestors,
+    /// and of the projects, keyed by their path.
+    pub fn known_guids(&self) -> KnownGuids {
+        let entries_by_guid = self
+            .entries
+            .iter()
+            .map(|entry| (entry.guid, entry))
+            .collect::<BTreeMap<_, _>>();
+
+        let folder_path = |entry: &Entry| -> String {
+            let mut path = entry.name.clone();
+            let mut guid = entry.guid;
+            // Bound the walk in case the nesting contains a cycle.
+            for _ in 0..self.entries.len() {
+                match self
+                    .nested_projects
+                    .get(&guid)
+                    .and_then(|parent| entries_by_guid.get(parent))
+                {
+                    Some(parent) => {
+                        path = join_str_path(&parent.name, &path);
+                        guid = parent.guid;
+                    }
+                    None => break,
+                }
+            }
+            path
+        };
+
+        self.entries
+            .iter()
+            .map(|entry| {
+                let path = if entry.is_folder() {
+                    folder_path(entry)
+                } else {
+                    entry.path.clone()
+                };
+                (normalise_path(&path), entry.guid)
+            })
+            .collect()
+    }
+
+    /// Iterates over the project entries, skipping solution folders.
+    pub fn projects(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter().filter(|entry| !entry.is_folder())
+    }
+
+    /// Resolves the path of an entry relative to the solution directory.
+    pub fn entry_path(&self, entry: &Entry) -> PathBuf {
+        let sln_dir = self.path.parent().unwrap();
+        sln_dir.join(native_separators(&entry.path)).simplify()
+    }
+}
+
+/// Parses the value of a `Key = Value` line after the key has been stripped.
+fn parse_value(rest: &str) -> Option<&str> {
+    rest.trim_start().strip_prefix('=').map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_projects_folders_and_nesting() {
+        let sln = ParsedSolution::parse(
+            PathBuf::from("All.sln"),
+            "\u{FEFF}
+Microsoft Visual Studio Solution File, Format Version 12.00
+# Visual Studio Version 17
+VisualStudioVersion = 17.0.31903.59
+MinimumVisualStudioVersion = 10.0.40219.1
+Project(\"{2150E333-8FDC-42A3-9474-1A3956D46DE8}\") = \"src\", \"src\", \"{0B2D1F6E-3A8C-4F43-9C3B-2F1E6C9A7D10}\"
+EndProject
+Project(\"{9A19103F-16F7-4668-BE54-9A1E7A4F7556}\") = \"App\", \"src\\App\\App.csproj\", \"{5A6C8E0B-1D2F-4A3B-8C9D-0E1F2A3B4C5D}\"
+EndProject
+Global
+\tGlobalSection(NestedProjects) = preSolution
+\t\t{5A6C8E0B-1D2F-4A3B-8C9D-0E1F2A3B4C5D} = {0B2D1F6E-3A8C-4F43-9C3B-2F1E6C9A7D10}
+\tEndGlobalSection
+\tGlobalSection(ExtensibilityGlobals) = postSolution
+\t\tSolutionGuid = {1C2D3E4F-5A6B-4C7D-8E9F-0A1B2C3D4E5F}
+\tEndGlobalSection
+EndGlobal
+",
+        );
+
+        assert_eq!(sln.visual_studio_version.as_deref(), Some("17.0.31903.59"));
+        assert_eq!(
+            sln.minimum_visual_studio_version.as_deref(),
+            Some("10.0.40219.1")
+        );
+        assert_eq!(
+            sln.solution_guid,
+            Uuid::parse_str("1C2D3E4F-5A6B-4C7D-8E9F-0A1B2C3D4E5F").ok()
+        );
+        assert_eq!(sln.entries.len(), 2);
+        assert!(sln.entries[0].is_folder());
+        let projects = sln.projects().collect::<Vec<_>>();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].name, "App");
+        assert_eq!(projects[0].path, "src\\App\\App.csproj");
+        assert_eq!(
+            projects[0].guid,
+            Uuid::parse_str("5A6C8E0B-1D2F-4A3B-8C9D-0E1F2A3B4C5D").unwrap()
+        );
+        assert_eq!(
+            sln.nested_projects.get(&projects[0].guid),
+            Some(&sln.entries[0].guid)
+        );
+
+        let known_guids = sln.known_guids();
+        assert_eq!(known_guids.get("src"), Some(&sln.entries[0].guid));
+        assert_eq!(
+            known_guids.get("src\\app\\app.csproj"),
+            Some(&projects[0].guid)
+        );
+    }
+}
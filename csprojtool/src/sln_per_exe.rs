@@ -0,0 +1,127 @@
+use log::debug;
+
+use crate::discovery::*;
+use crate::path_extensions::*;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options<'a> {
+    pub search_path: &'a Path,
+    pub out_dir: &'a Path,
+    /// The solution file name, `{name}` is replaced with the name of the executable project.
+    pub name_template: &'a str,
+    /// Reuse the guids of folders and projects found in existing solution files.
+    pub keep_guids: bool,
+    /// Path to a json file with additional build dependencies.
+    pub build_dependencies_path: Option<&'a Path>,
+    /// Overrides the VisualStudioVersion, otherwise it is taken from the existing solution files.
+    pub visual_studio_version: Option<&'a str>,
+}
+
+pub fn sln_per_exe(options: Options) {
+    debug!(
+        "Generating solutions per executable with options {:?}",
+        &options
+    );
+
+    let Options {
+        search_path,
+        out_dir,
+        name_template,
+        keep_guids,
+        build_dependencies_path,
+        visual_studio_version,
+    } = options;
+
+    let out_dir = out_dir.simplified_absolute().unwrap();
+    let current_dir = std::env::current_dir().unwrap();
+
+    let build_dependencies = build_dependencies_path
+        .map(crate::sln::read_build_dependencies)
+        .unwrap_or_default();
+
+    let Root {
+        root_dir,
+        search_path,
+    } = root_dir(search_path).unwrap();
+    let projects = find_projects(&root_dir);
+    let edges = project_edges(&projects, &root_dir);
+
+    let exes = projects
+        .iter()
+        .enumerate()
+        .filter(|(_, project)| project.is_exe && project.path.starts_with(&search_path))
+        .map(|(index, project)| {
+            let name = project.path.file_stem().unwrap().to_str().unwrap();
+            (index, out_dir.join(name_template.replace("{name}", name)))
+        })
+        .collect::<Vec<_>>();
+
+    // Executables with the same file name would silently overwrite each other's solution.
+    let mut exes_per_sln = BTreeMap::<&Path, Vec<&Path>>::new();
+    for (exe_index, sln_path) in exes.iter() {
+        exes_per_sln
+            .entry(sln_path)
+            .or_default()
+            .push(&projects[*exe_index].path);
+    }
+    for (sln_path, exe_paths) in exes_per_sln.iter_mut() {
+        exe_paths.sort();
+        if exe_paths.len() > 1 {
+            panic!(
+                "The executables {} would all be written to {}, narrow down the search path!",
+                exe_paths
+                    .iter()
+                    .map(|path| relative_path(&current_dir, path).display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                relative_path(&current_dir, sln_path).display()
+            );
+        }
+    }
+
+    std::fs::create_dir_all(&out_dir).unwrap();
+
+    for (exe_index, sln_path) in exes {
+        let mut included = vec![false; projects.len()];
+        included[exe_index] = true;
+        follow_project_references(&edges, &mut included, false, true);
+
+        // Add the test projects that reference anything in the closure, together with their own dependencies.
+        let tests = edges
+            .iter()
+            .filter(|&&(from, to)| included[to] && projects[from].is_test())
+            .map(|&(from, _)| from)
+            .collect::<Vec<_>>();
+        for test in tests {
+            included[test] = true;
+        }
+        follow_project_references(&edges, &mut included, false, true);
+
+        println!(
+            "Writing {} with {} projects",
+            relative_path(&current_dir, &sln_path).display(),
+            included.iter().filter(|&&included| included).count()
+        );
+
+        crate::sln::write_solution(
+            &sln_path,
+            projects
+                .iter()
+                .zip(included.iter())
+                .filter_map(|(project, &included)| {
+                    if included {
+                        Some(project.clone())
+                    } else {
+                        None
+                    }
+                }),
+            crate::sln::WriteOptions {
+                keep_guids,
+                build_dependencies: &build_dependencies,
+                visual_studio_version,
+            },
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! Target framework monikers and the rules that decide which frameworks a project can reference.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Family {
+    /// .NET Framework, `net20` up to `net481`.
+    NetFramework,
+    /// `netstandard1.0` up to `netstandard2.1`.
+    NetStandard,
+    /// .NET Core, `netcoreapp1.0` up to `netcoreapp3.1`.
+    NetCoreApp,
+    /// .NET 5 and later, `net5.0` and up, optionally platform specific like `net6.0-windows`.
+    Net,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetFramework {
+    pub family: Family,
+    /// Major, minor and patch version.
+    pub version: (u32, u32, u32),
+    /// The operating system of platform specific frameworks without its version, e.g. `windows`.
+    pub platform: Option<String>,
+}
+
+impl TargetFramework {
+    /// Parses a short folder name like `net472`, `netstandard2.0` or `net6.0-windows10.0.19041`. Returns `None`
+    /// for frameworks that are not .NET Framework, .NET Standard, .NET Core or .NET 5+.
+    pub fn parse(moniker: &str) -> Option<Self> {
+        let moniker = moniker.trim().to_ascii_lowercase();
+
+        if let Some(version) = moniker.strip_prefix("netstandard") {
+            return Some(Self::new(Family::NetStandard, parse_dotted(version)?));
+        }
+
+        if let Some(version) = moniker.strip_prefix("netcoreapp") {
+            return Some(Self::new(Family::NetCoreApp, parse_dotted(version)?));
+        }
+
+        let rest = moniker.strip_prefix("net")?;
+        if rest.contains('.') {
+            let (version, platform) = match rest.split_once('-') {
+                Some((version, platform)) => (version, Some(platform)),
+                None => (rest, None),
+            };
+            let version = parse_dotted(version)?;
+            if version.0 < 5 {
+                return None;
+            }
+            let platform = match platform {
+                Some(platform) => {
+                    let name = platform.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+                    if name.is_empty() {
+                        return None;
+                    }
+                    Some(name.to_string())
+                }
+                None => None,
+            };
+            Some(TargetFramework {
+                family: Family::Net,
+                version,
+                platform,
+            })
+        } else {
+            // .NET Framework versions are written without dots, one digit per component.
+            if !(2..=3).contains(&rest.len()) || !rest.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            let digit = |index: usize| {
+                rest.as_bytes()
+                    .get(index)
+                    .map_or(0, |&digit| (digit - b'0') as u32)
+            };
+            Some(Self::new(
+                Family::NetFramework,
+                (digit(0), digit(1), digit(2)),
+            ))
+        }
+    }
+
+    fn new(family: Family, version: (u32, u32, u32)) -> Self {
+        TargetFramework {
+            family,
+            version,
+            platform: None,
+        }
+    }
+
+    /// The highest .NET Standard version this framework implements.
+    fn net_standard_version(&self) -> Option<(u32, u32, u32)> {
+        let version = self.version;
+        match self.family {
+            Family::NetStandard => Some(version),
+            Family::NetFramework => match version {
+                v if v >= (4, 6, 1) => Some((2, 0, 0)),
+                v if v >= (4, 6, 0) => Some((1, 3, 0)),
+                v if v >= (4, 5, 1) => Some((1, 2, 0)),
+                v if v >= (4, 5, 0) => Some((1, 1, 0)),
+                _ => None,
+            },
+            Family::NetCoreApp => match version.0 {
+                1 => Some((1, 6, 0)),
+                2 => Some((2, 0, 0)),
+                _ => Some((2, 1, 0)),
+            },
+            Family::Net => Some((2, 1, 0)),
+        }
+    }
+
+    /// Whether a project targeting this framework can reference a project targeting the dependency framework.
+    pub fn can_reference(&self, dependency: &TargetFramework) -> bool {
+        let at_least = |version: (u32, u32, u32)| version >= dependency.version;
+        match dependency.family {
+            Family::NetFramework => self.family == Family::NetFramework && at_least(self.version),
+            Family::NetStandard => match self.net_standard_version() {
+                Some(version) => at_least(version),
+                None => false,
+            },
+            Family::NetCoreApp => match self.family {
+                Family::NetCoreApp => at_least(self.version),
+                Family::Net => true,
+                _ => false,
+            },
+            Family::Net => {
+                self.family == Family::Net
+                    && at_least(self.version)
+                    && (dependency.platform.is_none() || dependency.platform == self.platform)
+            }
+        }
+    }
+}
+
+fn parse_dotted(version: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = version.split('.').map(|part| part.parse::<u32>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+    let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+    Some((major, minor, patch))
+}
+
+/// Returns the frameworks of the referencing project for which none of the frameworks of the referenced project is
+/// compatible. Frameworks that are not understood are assumed to be compatible.
+pub fn incompatible_frameworks<'a>(
+    frameworks: &'a [String],
+    reference_frameworks: &[String],
+) -> Vec<&'a str> {
+    let reference_frameworks = reference_frameworks
+        .iter()
+        .map(|moniker| TargetFramework::parse(moniker))
+        .collect::<Option<Vec<_>>>();
+    let reference_frameworks = match reference_frameworks {
+        Some(reference_frameworks) if !reference_frameworks.is_empty() => reference_frameworks,
+        _ => return Vec::new(),
+    };
+
+    frameworks
+        .iter()
+        .filter(|moniker| match TargetFramework::parse(moniker) {
+            Some(framework) => !reference_frameworks
+                .iter()
+                .any(|reference_framework| framework.can_reference(reference_framework)),
+            None => false,
+        })
+        .map(String::as_str)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn can_reference(framework: &str, dependency: &str) -> bool {
+        TargetFramework::parse(framework)
+            .unwrap()
+            .can_reference(&TargetFramework::parse(dependency).unwrap())
+    }
+
+    #[test]
+    fn parse_understands_short_folder_names() {
+        assert_eq!(
+            TargetFramework::parse("net472"),
+            Some(TargetFramework::new(Family::NetFramework, (4, 7, 2)))
+        );
+        assert_eq!(
+            TargetFramework::parse("netstandard2.0"),
+            Some(TargetFramework::new(Family::NetStandard, (2, 0, 0)))
+        );
+        assert_eq!(
+            TargetFramework::parse("net6.0-windows10.0.19041"),
+            Some(TargetFramework {
+                family: Family::Net,
+                version: (6, 0, 0),
+                platform: Some("windows".to_string()),
+            })
+        );
+        assert_eq!(TargetFramework::parse("net4.8"), None);
+        assert_eq!(TargetFramework::parse("uap10.0"), None);
+    }
+
+    #[test]
+    fn can_reference_follows_compatibility_rules() {
+        assert!(can_reference("net48", "net472"));
+        assert!(!can_reference("net472", "net48"));
+        assert!(!can_reference("net6.0", "net48"));
+        assert!(!can_reference("net48", "net6.0"));
+        assert!(can_reference("net48", "netstandard2.0"));
+        assert!(!can_reference("net48", "netstandard2.1"));
+        assert!(!can_reference("net452", "netstandard2.0"));
+        assert!(can_reference("netcoreapp3.1", "netstandard2.1"));
+        assert!(can_reference("net6.0", "netcoreapp3.1"));
+        assert!(can_reference("net8.0", "net6.0"));
+        assert!(can_reference("net6.0-windows", "net6.0"));
+        assert!(!can_reference("net6.0", "net6.0-windows"));
+        assert!(!can_reference("netstandard2.0", "net6.0"));
+    }
+
+    #[test]
+    fn incompatible_frameworks_checks_every_framework() {
+        let frameworks = ["net48".to_string(), "net6.0".to_string()];
+        assert_eq!(
+            incompatible_frameworks(&frameworks, &["net6.0".to_string()]),
+            vec!["net48"]
+        );
+        assert!(incompatible_frameworks(
+            &frameworks,
+            &["net472".to_string(), "netstandard2.0".to_string()]
+        )
+        .is_empty());
+        assert!(incompatible_frameworks(&frameworks, &["uap10.0".to_string()]).is_empty());
+    }
+}
@@ -1,20 +1,13 @@
+use log::debug;
 use std::{
     ffi::OsStr,
     path::{Path, PathBuf},
+    process::Command,
 };
 
+/// Finds the closest ancestor containing a `.git` directory, or a `.git` file for worktrees and submodules.
 pub fn find_git_root(dir: &Path) -> Option<&Path> {
-    dir.ancestors().find(|&dir| dir_contains_git(dir))
-}
-
-fn dir_contains_git(dir: &Path) -> bool {
-    std::fs::read_dir(dir)
-        .unwrap()
-        .any(|entry| entry_is_git(&entry.unwrap()))
-}
-
-fn entry_is_git(entry: &std::fs::DirEntry) -> bool {
-    entry.file_type().unwrap().is_dir() && entry.file_name() == ".git"
+    dir.ancestors().find(|&dir| dir.join(".git").exists())
 }
 
 pub fn find_dir_csproj(dir: &Path) -> impl Iterator<Item = PathBuf> {
@@ -32,10 +25,59 @@ pub fn path_extension_is_csproj(path: &Path) -> bool {
     path.extension() == Some(OsStr::new("csproj"))
 }
 
+pub fn path_extension_is_sln(path: &Path) -> bool {
+    path.extension() == Some(OsStr::new("sln"))
+}
+
 fn std_entry_is_csproj(entry: &std::fs::DirEntry) -> bool {
     entry.file_type().unwrap().is_file() && path_extension_is_csproj(entry.file_name().as_ref())
 }
 
 pub fn entry_is_csproj(entry: &ignore::DirEntry) -> bool {
-    entry.file_type().unwrap().is_file() && path_extension_is_csproj(entry.file_name().as_ref())
+    entry_is_file(entry) && path_extension_is_csproj(entry.file_name().as_ref())
+}
+
+pub fn entry_is_sln(entry: &ignore::DirEntry) -> bool {
+    entry_is_file(entry) && path_extension_is_sln(entry.file_name().as_ref())
+}
+
+fn entry_is_file(entry: &ignore::DirEntry) -> bool {
+    entry
+        .file_type()
+        .map(|file_type| file_type.is_file())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            other => Err(format!("Unknown output format {}", other)),
+        }
+    }
+}
+
+/// Runs git in the directory and returns its standard output, panics when git fails.
+pub fn git(dir: &Path, args: &[&str]) -> String {
+    let mut command = Command::new("git");
+    command.current_dir(dir).args(args);
+    debug!("{:?}", &command);
+    let output = command.output().expect("failed to run git");
+    if !output.status.success() {
+        panic!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    String::from_utf8(output.stdout).unwrap()
 }
@@ -0,0 +1,167 @@
+use serde::Serialize;
+
+use crate::discovery::*;
+use crate::path_extensions::*;
+use crate::utils::OutputFormat;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options<'a> {
+    pub from: &'a Path,
+    /// A project path or, if no such file exists, a package name.
+    pub to: &'a str,
+    /// Only report this many of the shortest paths, `usize::MAX` reports all of them.
+    pub limit: usize,
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub from: PathBuf,
+    pub to: String,
+    pub paths: Vec<ReferencePath>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReferencePath {
+    pub projects: Vec<PathBuf>,
+    /// The version of the package reference that ends the path, when explaining a package.
+    pub package_version: Option<String>,
+}
+
+pub fn run(options: Options) {
+    let report = why(options);
+
+    match options.format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(std::io::stdout(), &report).unwrap();
+            println!();
+        }
+        OutputFormat::Table => print_table(&report),
+    }
+}
+
+pub fn why(options: Options) -> Report {
+    let Options {
+        from, to, limit, ..
+    } = options;
+
+    let current_dir = std::env::current_dir().unwrap();
+    let Root {
+        root_dir,
+        search_path: from,
+    } = root_dir(from).expect("Failed to canonicalize path, does it exist?");
+
+    let mut projects = Vec::new();
+    let mut errors = BTreeMap::new();
+    for (path, project) in find_and_parse_projects(&root_dir) {
+        match project {
+            Ok(project) => projects.push(project),
+            Err(e) => {
+                errors.insert(path, e);
+            }
+        }
+    }
+    projects.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let edges = project_edges(&projects, &root_dir);
+    let outgoing = crate::graph::outgoing_edges(projects.len(), &edges);
+
+    let index_of = |path: &Path| {
+        projects
+            .iter()
+            .position(|project| project.path == path)
+            .unwrap_or_else(|| {
+                let rel_path = relative_path(&current_dir, path);
+                match errors.get(path) {
+                    Some(e) => eprintln!("Failed to parse {}: {}", rel_path.display(), e),
+                    None => eprintln!(
+                        "{} is not a project under {}",
+                        rel_path.display(),
+                        root_dir.display()
+                    ),
+                }
+                std::process::exit(1);
+            })
+    };
+
+    let from_index = index_of(&from);
+
+    let (to_display, targets, package) = match std::fs::canonicalize(to) {
+        Ok(to_path) if to_path.is_file() => {
+            let to_index = index_of(&to_path);
+            (
+                relative_path(&current_dir, &to_path).display().to_string(),
+                vec![to_index],
+                None,
+            )
+        }
+        _ => {
+            let targets = projects
+                .iter()
+                .enumerate()
+                .filter(|(_, project)| {
+                    project
+                        .package_references
+                        .iter()
+                        .any(|package| package.name.eq_ignore_ascii_case(to))
+                })
+                .map(|(index, _)| index)
+                .collect();
+            (to.to_string(), targets, Some(to))
+        }
+    };
+
+    let paths = crate::graph::paths(&outgoing, from_index, &targets, limit)
+        .into_iter()
+        .map(|path| {
+            let package_version = package.and_then(|name| {
+                projects[*path.last().unwrap()]
+                    .package_references
+                    .iter()
+                    .find(|package| package.name.eq_ignore_ascii_case(name))
+                    .map(|package| package.version.clone())
+            });
+            ReferencePath {
+                projects: path
+                    .into_iter()
+                    .map(|index| relative_path(&current_dir, &projects[index].path))
+                    .collect(),
+                package_version,
+            }
+        })
+        .collect();
+
+    Report {
+        from: relative_path(&current_dir, &from),
+        to: to_display,
+        paths,
+    }
+}
+
+fn print_table(report: &Report) {
+    if report.paths.is_empty() {
+        println!("{} does not reference {}", report.from.display(), report.to);
+        return;
+    }
+
+    println!(
+        "{} path(s) from {} to {}:",
+        report.paths.len(),
+        report.from.display(),
+        report.to
+    );
+    for path in report.paths.iter() {
+        let mut steps = path
+            .projects
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>();
+        if let Some(version) = &path.package_version {
+            steps.push(format!("{} {}", report.to, version));
+        }
+        println!("  {}", steps.join(" -> "));
+    }
+}
@@ -80,31 +80,43 @@ pub fn transform_xml_file<F>(file_path: &Path, transform: F) -> Result<(), Error
 where
     F: FnOnce(Element) -> Option<Element>,
 {
-    let dir_path = file_path.parent().unwrap();
-
     if let Some(root) = transform(read_xml_file(file_path)?) {
-        let mut writer = std::io::BufWriter::new(tempfile::NamedTempFile::new_in(dir_path)?);
-
-        let write_document_declaration = root.attributes.get("Sdk").is_none();
-
-        root.write_with_config(
-            &mut writer,
-            xmltree::EmitterConfig {
-                perform_escaping: true,
-                perform_indent: true,
-                write_document_declaration,
-                line_separator: "\r\n".into(),
-                ..Default::default()
-            },
-        )
-        .unwrap();
-
-        writer.into_inner().unwrap().persist(&file_path)?;
+        write_xml_file(file_path, &root)?;
     }
 
     Ok(())
 }
 
+/// Serializes the document the way it is written to project files.
+pub fn xml_to_bytes(root: &Element) -> Vec<u8> {
+    let write_document_declaration = root.attributes.get("Sdk").is_none();
+
+    let mut bytes = Vec::new();
+    root.write_with_config(
+        &mut bytes,
+        xmltree::EmitterConfig {
+            perform_escaping: true,
+            perform_indent: true,
+            write_document_declaration,
+            line_separator: "\r\n".into(),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    bytes
+}
+
+/// Replaces the file through a temporary file in the same directory.
+pub fn write_xml_file(file_path: &Path, root: &Element) -> Result<(), Error> {
+    let dir_path = file_path.parent().unwrap();
+
+    let mut file = tempfile::NamedTempFile::new_in(dir_path)?;
+    std::io::Write::write_all(&mut file, &xml_to_bytes(root))?;
+    file.persist(file_path)?;
+
+    Ok(())
+}
+
 fn node_as_element(node: &XMLNode) -> Option<&Element> {
     match node {
         XMLNode::Element(element) => Some(element),